@@ -0,0 +1,364 @@
+//! `#[derive(ToCandy, FromCandy)]` for converting structs and enums to and from `ic_candy`'s `CandyShared`.
+//!
+//! * Structs with named fields become a `Class` with one `PropertyShared` per field, named after the field.
+//!   Tuple structs name the properties `"0"`, `"1"`, ..., newtype structs convert like their only field and
+//!   unit structs become `Option(None)`.
+//! * Enums become a `Class` tagged with the variant name, the shape `ic_candy::idl::from_idl_value` gives
+//!   Candid variants: the immutable properties `"variant"`, holding the name as `Text`, and `"value"`,
+//!   holding the fields converted like a struct's.
+//!
+//! `ToCandy` implements `From<T> for CandyShared` and `ToCandyValue`, converting the fields with
+//! `ToCandyValue`. `FromCandy` implements `FromCandyValue` and `TryFrom<CandyShared>` with `CandyError` as the
+//! error, converting the fields with `FromCandyValue`, so that they can be `Vec`s, `Option`s, maps and other
+//! derived types. It ignores unknown properties and fails with
+//! `CandyError::MissingField` for a missing one and `CandyError::InvalidField` for one that does not convert.
+//!
+//! Fields take the attributes:
+//!
+//! * `#[candy(rename = "name")]` to name the property differently, also allowed on enum variants,
+//! * `#[candy(immutable)]` to mark the property immutable,
+//! * `#[candy(skip)]` to leave the field out, `FromCandy` then sets it to `Default::default()`.
+//!
+//! ```
+//! use ic_candy::types::{CandyError, PropertyShared};
+//! use ic_candy::value::{CandyShared, ToCandyValue};
+//! use ic_candy_derive::{FromCandy, ToCandy};
+//!
+//! #[derive(Debug, PartialEq, ToCandy, FromCandy)]
+//! struct Token {
+//!     #[candy(immutable)]
+//!     id: u64,
+//!     #[candy(rename = "display_name")]
+//!     name: String,
+//!     #[candy(skip)]
+//!     cache: Vec<u8>,
+//! }
+//!
+//! let token = Token { id: 1, name: "candy".to_string(), cache: vec![1] };
+//! let value = token.to_candy();
+//! assert_eq!(
+//!     value,
+//!     vec![
+//!         PropertyShared { name: "id".to_string(), value: 1_u64.to_candy(), immutable: true },
+//!         PropertyShared { name: "display_name".to_string(), value: "candy".to_candy(), immutable: false },
+//!     ]
+//!     .to_candy()
+//! );
+//! assert_eq!(Token::try_from(value), Ok(Token { id: 1, name: "candy".to_string(), cache: vec![] }));
+//! assert_eq!(Token::try_from(1_u8.to_candy()), Err(CandyError::UnsupportedVariant("Nat8".to_string())));
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, Generics, LitStr, Member,
+    Path, WherePredicate,
+};
+
+#[proc_macro_derive(ToCandy, attributes(candy))]
+pub fn derive_to_candy(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    to_candy(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(FromCandy, attributes(candy))]
+pub fn derive_from_candy(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_candy(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct CandyAttrs {
+    rename: Option<String>,
+    immutable: bool,
+    skip: bool,
+}
+
+fn candy_attrs(attrs: &[Attribute]) -> syn::Result<CandyAttrs> {
+    let mut candy = CandyAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("candy")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                candy.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("immutable") {
+                candy.immutable = true;
+            } else if meta.path.is_ident("skip") {
+                candy.skip = true;
+            } else {
+                return Err(meta.error("expected `rename`, `immutable` or `skip`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(candy)
+}
+
+fn variant_name(variant: &syn::Variant) -> syn::Result<String> {
+    let candy = candy_attrs(&variant.attrs)?;
+    if candy.immutable || candy.skip {
+        return Err(syn::Error::new_spanned(
+            variant,
+            "enum variants only take `#[candy(rename = \"...\")]`",
+        ));
+    }
+    Ok(candy.rename.unwrap_or_else(|| unraw(&variant.ident)))
+}
+
+struct Field {
+    member: Member,
+    binding: Ident,
+    name: String,
+    immutable: bool,
+    skip: bool,
+}
+
+enum Shape {
+    Unit,
+    Newtype(Field),
+    Class(Vec<Field>),
+}
+
+fn shape(fields: &Fields) -> syn::Result<Shape> {
+    let mut parsed = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let candy = candy_attrs(&field.attrs)?;
+        let (member, default_name) = match &field.ident {
+            Some(ident) => (Member::Named(ident.clone()), unraw(ident)),
+            None => (Member::Unnamed(index.into()), index.to_string()),
+        };
+        parsed.push(Field {
+            member,
+            binding: format_ident!("__field{}", index),
+            name: candy.rename.unwrap_or(default_name),
+            immutable: candy.immutable,
+            skip: candy.skip,
+        });
+    }
+    Ok(match fields {
+        Fields::Unit => Shape::Unit,
+        Fields::Unnamed(unnamed) if parsed.len() == 1 => {
+            if !unnamed.unnamed[0]
+                .attrs
+                .iter()
+                .any(|attr| attr.path().is_ident("candy"))
+            {
+                Shape::Newtype(parsed.remove(0))
+            } else {
+                return Err(syn::Error::new_spanned(
+                    unnamed,
+                    "newtype fields convert like their value and take no `#[candy]` attributes",
+                ));
+            }
+        }
+        _ => Shape::Class(parsed),
+    })
+}
+
+fn unraw(ident: &Ident) -> String {
+    ident.to_string().trim_start_matches("r#").to_string()
+}
+
+fn add_bounds(generics: &mut Generics, bound: TokenStream2) {
+    let params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        let predicate: WherePredicate = parse_quote!(#param: #bound);
+        where_clause.predicates.push(predicate);
+    }
+}
+
+// Destructures a value of the struct or variant at `path`, binding the fields that are not skipped.
+fn pattern(path: &Path, shape: &Shape) -> TokenStream2 {
+    let fields: Vec<&Field> = match shape {
+        Shape::Unit => Vec::new(),
+        Shape::Newtype(field) => vec![field],
+        Shape::Class(fields) => fields.iter().filter(|field| !field.skip).collect(),
+    };
+    let members = fields.iter().map(|field| &field.member);
+    let bindings = fields.iter().map(|field| &field.binding);
+    quote!(#path { #(#members: #bindings,)* .. })
+}
+
+fn shape_to_candy(shape: &Shape) -> TokenStream2 {
+    match shape {
+        Shape::Unit => quote!(::ic_candy::value::CandyShared::Option(
+            ::core::option::Option::None
+        )),
+        Shape::Newtype(field) => {
+            let binding = &field.binding;
+            quote!(::ic_candy::value::ToCandyValue::to_candy(#binding))
+        }
+        Shape::Class(fields) => {
+            let properties = fields.iter().filter(|field| !field.skip).map(|field| {
+                let Field {
+                    binding,
+                    name,
+                    immutable,
+                    ..
+                } = field;
+                quote! {
+                    ::ic_candy::types::PropertyShared {
+                        name: ::std::string::ToString::to_string(#name),
+                        value: ::ic_candy::value::ToCandyValue::to_candy(#binding),
+                        immutable: #immutable,
+                    }
+                }
+            });
+            quote!(::ic_candy::value::CandyShared::Class(
+                ::std::vec![#(#properties),*]
+            ))
+        }
+    }
+}
+
+// Builds the struct or variant at `path` from the `CandyShared` named `value`, `context` names a newtype
+// variant in errors.
+fn shape_from_candy(path: &Path, shape: &Shape, context: Option<&str>) -> TokenStream2 {
+    match shape {
+        Shape::Unit => quote! {
+            ::ic_candy::derive::unit(value)?;
+            ::core::result::Result::Ok(#path {})
+        },
+        Shape::Newtype(field) => {
+            let member = &field.member;
+            let convert = match context {
+                Some(name) => quote!(::ic_candy::derive::value(#name, value)?),
+                None => quote!(::ic_candy::value::FromCandyValue::from_candy(value)?),
+            };
+            quote!(::core::result::Result::Ok(#path { #member: #convert }))
+        }
+        Shape::Class(fields) => {
+            let members = fields.iter().map(|field| {
+                let Field {
+                    member, name, skip, ..
+                } = field;
+                match skip {
+                    true => quote!(#member: ::core::default::Default::default()),
+                    false => {
+                        quote!(#member: ::ic_candy::derive::field(&mut properties, #name)?)
+                    }
+                }
+            });
+            quote! {
+                #[allow(unused_mut)]
+                let mut properties = ::ic_candy::derive::class(value)?;
+                ::core::result::Result::Ok(#path { #(#members,)* })
+            }
+        }
+    }
+}
+
+fn to_candy(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let shape = shape(&data.fields)?;
+            let pattern = pattern(&parse_quote!(#ident), &shape);
+            let candy = shape_to_candy(&shape);
+            quote! {
+                let #pattern = value;
+                #candy
+            }
+        }
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let name = variant_name(variant)?;
+                    let shape = shape(&variant.fields)?;
+                    let variant = &variant.ident;
+                    let pattern = pattern(&parse_quote!(#ident::#variant), &shape);
+                    let candy = shape_to_candy(&shape);
+                    Ok(quote!(#pattern => ::ic_candy::derive::variant(#name, #candy),))
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote!(match value { #(#arms)* })
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "ToCandy can not be derived for unions",
+            ))
+        }
+    };
+
+    add_bounds(&mut input.generics, quote!(::ic_candy::value::ToCandyValue));
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::core::convert::From<#ident #ty_generics> for ::ic_candy::value::CandyShared #where_clause {
+            fn from(value: #ident #ty_generics) -> Self {
+                #body
+            }
+        }
+
+        impl #impl_generics ::ic_candy::value::ToCandyValue for #ident #ty_generics #where_clause {
+            fn to_candy(self) -> ::ic_candy::value::CandyShared {
+                ::ic_candy::value::CandyShared::from(self)
+            }
+        }
+    })
+}
+
+fn from_candy(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let body = match &input.data {
+        Data::Struct(data) => shape_from_candy(&parse_quote!(#ident), &shape(&data.fields)?, None),
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let name = variant_name(variant)?;
+                    let shape = shape(&variant.fields)?;
+                    let variant = &variant.ident;
+                    let build =
+                        shape_from_candy(&parse_quote!(#ident::#variant), &shape, Some(&name));
+                    Ok(quote!(#name => { #build }))
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote! {
+                let (label, value) = ::ic_candy::derive::untag(value)?;
+                match label.as_str() {
+                    #(#arms)*
+                    _ => ::core::result::Result::Err(::ic_candy::derive::unknown_variant(&label)),
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "FromCandy can not be derived for unions",
+            ))
+        }
+    };
+
+    add_bounds(
+        &mut input.generics,
+        quote!(::ic_candy::value::FromCandyValue),
+    );
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::ic_candy::value::FromCandyValue for #ident #ty_generics #where_clause {
+            fn from_candy(value: ::ic_candy::value::CandyShared) -> ::core::result::Result<Self, ::ic_candy::types::CandyError> {
+                #body
+            }
+        }
+
+        impl #impl_generics ::core::convert::TryFrom<::ic_candy::value::CandyShared> for #ident #ty_generics #where_clause {
+            type Error = ::ic_candy::types::CandyError;
+
+            fn try_from(value: ::ic_candy::value::CandyShared) -> ::core::result::Result<Self, Self::Error> {
+                ::ic_candy::value::FromCandyValue::from_candy(value)
+            }
+        }
+    })
+}
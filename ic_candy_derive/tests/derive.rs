@@ -0,0 +1,284 @@
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod derive_tests {
+    use std::collections::BTreeMap;
+
+    use candid::types::TypeEnv;
+    use candid::{CandidType, IDLArgs, Principal};
+    use pretty_assertions::assert_eq;
+
+    use ic_candy::idl::from_idl_value;
+    use ic_candy::types::{CandyError, PropertyShared};
+    use ic_candy::value::{CandyShared, ToCandyValue};
+    use ic_candy_derive::{FromCandy, ToCandy};
+
+    #[derive(Clone, Debug, PartialEq, CandidType, ToCandy, FromCandy)]
+    struct Owner {
+        principal: Principal,
+        subaccount: Vec<u8>,
+    }
+
+    #[derive(Clone, Debug, PartialEq, CandidType, ToCandy, FromCandy)]
+    enum Status {
+        Minted,
+        #[candy(rename = "Burned")]
+        Destroyed(u64),
+        Listed(u64, String),
+        Transferred {
+            from: Owner,
+            to: Owner,
+        },
+    }
+
+    #[derive(Clone, Debug, PartialEq, ToCandy, FromCandy)]
+    struct Token {
+        #[candy(immutable)]
+        id: candid::Nat,
+        #[candy(rename = "owner_account")]
+        owner: Owner,
+        status: Status,
+        r#type: String,
+        #[candy(skip)]
+        cached: u32,
+    }
+
+    #[derive(Debug, PartialEq, ToCandy, FromCandy)]
+    struct Id(u64);
+
+    #[derive(Debug, PartialEq, ToCandy, FromCandy)]
+    struct Pair(u8, #[candy(rename = "second")] bool);
+
+    #[derive(Debug, PartialEq, ToCandy, FromCandy)]
+    struct Marker;
+
+    #[derive(Debug, PartialEq, FromCandy)]
+    struct Collection {
+        owners: Vec<Owner>,
+        royalty: Option<u16>,
+        statuses: BTreeMap<String, Status>,
+    }
+
+    #[derive(Debug, PartialEq, ToCandy, FromCandy)]
+    struct Wrapper<T> {
+        inner: T,
+    }
+
+    fn prop(name: &str, value: CandyShared, immutable: bool) -> PropertyShared {
+        PropertyShared {
+            name: name.to_string(),
+            value,
+            immutable,
+        }
+    }
+
+    fn tagged(label: &str, value: CandyShared) -> CandyShared {
+        vec![
+            prop("variant", label.to_candy(), true),
+            prop("value", value, true),
+        ]
+        .to_candy()
+    }
+
+    fn owner() -> Owner {
+        Owner {
+            principal: Principal::anonymous(),
+            subaccount: vec![0; 2],
+        }
+    }
+
+    fn owner_candy() -> CandyShared {
+        vec![
+            prop("principal", Principal::anonymous().to_candy(), false),
+            prop("subaccount", vec![0_u8; 2].to_candy(), false),
+        ]
+        .to_candy()
+    }
+
+    #[test]
+    fn derive_struct() {
+        let token = Token {
+            id: 7_u8.into(),
+            owner: owner(),
+            status: Status::Minted,
+            r#type: "nft".to_string(),
+            cached: 1,
+        };
+        let value = token.clone().to_candy();
+        assert_eq!(
+            value,
+            vec![
+                prop("id", 7_u128.to_candy(), true),
+                prop("owner_account", owner_candy(), false),
+                prop("status", tagged("Minted", CandyShared::Option(None)), false),
+                prop("type", "nft".to_candy(), false),
+            ]
+            .to_candy()
+        );
+        assert_eq!(Token::try_from(value), Ok(Token { cached: 0, ..token }));
+    }
+
+    #[test]
+    fn derive_tuple_structs() {
+        assert_eq!(Id(3).to_candy(), 3_u64.to_candy());
+        assert_eq!(Id::try_from(3_u8.to_candy()), Ok(Id(3)));
+
+        let pair = vec![
+            prop("0", 1_u8.to_candy(), false),
+            prop("second", true.to_candy(), false),
+        ]
+        .to_candy();
+        assert_eq!(Pair(1, true).to_candy(), pair);
+        assert_eq!(Pair::try_from(pair), Ok(Pair(1, true)));
+
+        assert_eq!(Marker.to_candy(), CandyShared::Option(None));
+        assert_eq!(Marker::try_from(CandyShared::Option(None)), Ok(Marker));
+
+        let wrapper = Wrapper { inner: Id(1) };
+        assert_eq!(
+            wrapper.to_candy(),
+            vec![prop("inner", 1_u64.to_candy(), false)].to_candy()
+        );
+    }
+
+    #[test]
+    fn derive_enum() {
+        let statuses = [
+            (Status::Minted, tagged("Minted", CandyShared::Option(None))),
+            (Status::Destroyed(5), tagged("Burned", 5_u64.to_candy())),
+            (
+                Status::Listed(9, "ICP".to_string()),
+                tagged(
+                    "Listed",
+                    vec![
+                        prop("0", 9_u64.to_candy(), false),
+                        prop("1", "ICP".to_candy(), false),
+                    ]
+                    .to_candy(),
+                ),
+            ),
+            (
+                Status::Transferred {
+                    from: owner(),
+                    to: owner(),
+                },
+                tagged(
+                    "Transferred",
+                    vec![
+                        prop("from", owner_candy(), false),
+                        prop("to", owner_candy(), false),
+                    ]
+                    .to_candy(),
+                ),
+            ),
+        ];
+        for (status, value) in statuses {
+            assert_eq!(status.clone().to_candy(), value);
+            assert_eq!(Status::try_from(value), Ok(status));
+        }
+    }
+
+    #[test]
+    fn derive_nested_fields() {
+        let mut statuses = BTreeMap::<String, CandyShared>::new();
+        statuses.insert("1".to_string(), Status::Destroyed(2).to_candy());
+        let value = vec![
+            prop("owners", vec![owner_candy()].to_candy(), false),
+            prop(
+                "royalty",
+                CandyShared::Option(Some(Box::new(50_u8.to_candy()))),
+                false,
+            ),
+            prop("statuses", statuses.to_candy(), false),
+        ]
+        .to_candy();
+        assert_eq!(
+            Collection::try_from(value),
+            Ok(Collection {
+                owners: vec![owner()],
+                royalty: Some(50),
+                statuses: BTreeMap::from([("1".to_string(), Status::Destroyed(2))]),
+            })
+        );
+    }
+
+    // Enums convert like their Candid encoding does through `idl::from_idl_value`, up to the order of
+    // record fields, which Candid sorts by their id.
+    #[test]
+    fn derive_matches_candid() {
+        for status in [Status::Minted, Status::Listed(9, "ICP".to_string())] {
+            let bytes = candid::encode_one(&status).unwrap();
+            let args =
+                IDLArgs::from_bytes_with_types(&bytes, &TypeEnv::new(), &[Status::ty()]).unwrap();
+            assert_eq!(from_idl_value(args.args[0].clone()), Ok(status.to_candy()));
+        }
+    }
+
+    #[test]
+    fn derive_errors() {
+        assert_eq!(
+            Owner::try_from(
+                vec![prop("principal", Principal::anonymous().to_candy(), false)].to_candy()
+            ),
+            Err(CandyError::MissingField("subaccount".to_string()))
+        );
+
+        let value = vec![
+            prop("from", owner_candy(), false),
+            prop(
+                "to",
+                vec![
+                    prop("principal", "aaaaa-aa".to_candy(), false),
+                    prop("subaccount", vec![0_u8].to_candy(), false),
+                ]
+                .to_candy(),
+                false,
+            ),
+        ]
+        .to_candy();
+        let error = Status::try_from(tagged("Transferred", value)).unwrap_err();
+        assert_eq!(
+            error,
+            CandyError::InvalidField(
+                "to".to_string(),
+                Box::new(CandyError::InvalidField(
+                    "principal".to_string(),
+                    Box::new(CandyError::UnsupportedVariant("Text".to_string()))
+                ))
+            )
+        );
+        assert_eq!(
+            error.to_string(),
+            "invalid field to: invalid field principal: unsupported variant Text"
+        );
+
+        assert_eq!(
+            Status::try_from(tagged("Burned", (-1_i8).to_candy())),
+            Err(CandyError::InvalidField(
+                "Burned".to_string(),
+                Box::new(CandyError::Overflow)
+            ))
+        );
+        // Amounts are never rounded.
+        assert_eq!(
+            Status::try_from(tagged("Burned", 1.6_f64.to_candy())),
+            Err(CandyError::InvalidField(
+                "Burned".to_string(),
+                Box::new(CandyError::Overflow)
+            ))
+        );
+        assert_eq!(
+            Wrapper::<u64>::try_from(vec![prop("inner", 1.6_f64.to_candy(), false)].to_candy()),
+            Err(CandyError::InvalidField(
+                "inner".to_string(),
+                Box::new(CandyError::Overflow)
+            ))
+        );
+        assert_eq!(
+            Status::try_from(tagged("Destroyed", 1_u8.to_candy())),
+            Err(CandyError::InvalidEncoding(
+                "unknown variant Destroyed".to_string()
+            ))
+        );
+    }
+}
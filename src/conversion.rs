@@ -0,0 +1,116 @@
+use candid::Principal;
+use num_bigint::{BigInt, BigUint};
+
+use crate::types::CandyError;
+
+/// Decodes a UTF-32BE blob produced by `CandyShared::Text(..).to_blob()`.
+///
+/// ```
+/// use ic_candy::conversion::blob_to_text;
+/// use ic_candy::value::ToCandyValue;
+///
+/// let blob = "Hello".to_candy().try_to_blob().unwrap();
+/// assert_eq!(blob_to_text(&blob), Ok("Hello".to_string()));
+/// ```
+pub fn blob_to_text(blob: &[u8]) -> Result<String, CandyError> {
+    if !blob.len().is_multiple_of(4) {
+        return Err(CandyError::InvalidEncoding(format!(
+            "text blob length {} is not a multiple of 4",
+            blob.len()
+        )));
+    }
+    blob.chunks_exact(4)
+        .map(|chunk| {
+            let code = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            char::from_u32(code).ok_or_else(|| {
+                CandyError::InvalidEncoding(format!("{:#x} is not a valid unicode scalar", code))
+            })
+        })
+        .collect()
+}
+
+/// Decodes a big-endian base-256 blob produced by `ToBlob for BigUint`.
+///
+/// An empty blob decodes to zero, like Motoko's `Conversion.bytesToNat`.
+///
+/// ```
+/// use ic_candy::conversion::blob_to_nat;
+/// use num_bigint::BigUint;
+///
+/// assert_eq!(blob_to_nat(&[1, 0]), Ok(BigUint::from(256_u32)));
+/// ```
+pub fn blob_to_nat(blob: &[u8]) -> Result<BigUint, CandyError> {
+    Ok(BigUint::from_bytes_be(blob))
+}
+
+/// Decodes a blob produced by `ToBlob for BigInt`: a sign byte (`0` positive, `1` negative)
+/// followed by big-endian base-128 digits.
+///
+/// ```
+/// use ic_candy::conversion::blob_to_int;
+/// use num_bigint::BigInt;
+///
+/// assert_eq!(blob_to_int(&[1, 123]), Ok(BigInt::from(-123)));
+/// ```
+pub fn blob_to_int(blob: &[u8]) -> Result<BigInt, CandyError> {
+    let (sign, digits) = match blob.split_first() {
+        Some((sign, digits)) if !digits.is_empty() => (*sign, digits),
+        _ => {
+            return Err(CandyError::InvalidEncoding(
+                "int blob needs a sign byte and at least one digit".to_string(),
+            ))
+        }
+    };
+    if sign > 1 {
+        return Err(CandyError::InvalidEncoding(format!(
+            "invalid int sign byte {}",
+            sign
+        )));
+    }
+    if let Some(digit) = digits.iter().find(|d| **d >= 128) {
+        return Err(CandyError::InvalidEncoding(format!(
+            "invalid base-128 digit {}",
+            digit
+        )));
+    }
+    let magnitude = BigInt::from_radix_be(num_bigint::Sign::Plus, digits, 128)
+        .expect("digits are checked to be below radix");
+    Ok(if sign == 1 { -magnitude } else { magnitude })
+}
+
+macro_rules! blob_to_nat_of_size {
+    ($($name:ident => $t:ty, $size:literal),*) => {
+        $(
+        #[doc = concat!("Decodes a big-endian `", stringify!($t), "` blob of exactly ", stringify!($size), " byte(s).")]
+        pub fn $name(blob: &[u8]) -> Result<$t, CandyError> {
+            blob.try_into().map(<$t>::from_be_bytes).map_err(|_| {
+                CandyError::InvalidEncoding(format!(
+                    "expected {} bytes for {}, got {}",
+                    $size,
+                    stringify!($t),
+                    blob.len()
+                ))
+            })
+        }
+        )*
+    };
+}
+
+blob_to_nat_of_size!(
+    blob_to_nat8 => u8, 1,
+    blob_to_nat16 => u16, 2,
+    blob_to_nat32 => u32, 4,
+    blob_to_nat64 => u64, 8
+);
+
+/// Decodes the raw principal bytes produced by `CandyShared::Principal(..).to_blob()`.
+///
+/// ```
+/// use candid::Principal;
+/// use ic_candy::conversion::blob_to_principal;
+///
+/// assert_eq!(blob_to_principal(&[4]), Ok(Principal::anonymous()));
+/// ```
+pub fn blob_to_principal(blob: &[u8]) -> Result<Principal, CandyError> {
+    Principal::try_from_slice(blob).map_err(|e| CandyError::InvalidEncoding(e.to_string()))
+}
@@ -0,0 +1,360 @@
+use std::fmt::Display;
+
+use num_traits::ToPrimitive;
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::derive::untag;
+use crate::types::CandyError;
+use crate::value::CandyShared;
+
+impl de::Error for CandyError {
+    fn custom<T: Display>(msg: T) -> Self {
+        CandyError::Custom(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        CandyError::MissingField(field.to_string())
+    }
+}
+
+/// Converts a `CandyShared` into any `Deserialize` type, reading the shapes `to_candy_value` writes.
+/// It also accepts:
+///
+/// * the number and collection variants of other widths and kinds, as long as the values fit,
+///   e.g. `Nats`, `Ints`, `Floats`, `Blob`, `Bytes` and `Set` for sequences,
+/// * a `Class` for maps, with its property names as keys, and for tuples, with the properties in order,
+/// * a `Principal` as its bytes, and a plain `Text` for a unit enum variant,
+/// * any value other than `Option` for an `Option` field, as `Some`.
+///
+/// ```
+/// use ic_candy::from_candy_value;
+/// use ic_candy::types::{CandyError, PropertyShared};
+/// use ic_candy::value::{CandyShared, ToCandyValue};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Listing {
+///     token_id: u64,
+///     price: Option<u128>,
+///     tags: Vec<String>,
+/// }
+///
+/// let value = vec![
+///     PropertyShared { name: "token_id".to_string(), value: 1_u8.to_candy(), immutable: true },
+///     PropertyShared { name: "price".to_string(), value: 5_u128.to_candy(), immutable: false },
+///     PropertyShared { name: "tags".to_string(), value: CandyShared::Array(vec![]), immutable: false },
+/// ]
+/// .to_candy();
+/// assert_eq!(
+///     from_candy_value::<Listing>(value),
+///     Ok(Listing { token_id: 1, price: Some(5), tags: vec![] })
+/// );
+/// assert_eq!(
+///     from_candy_value::<Listing>(CandyShared::Class(vec![])),
+///     Err(CandyError::MissingField("token_id".to_string()))
+/// );
+/// ```
+pub fn from_candy_value<T>(value: CandyShared) -> Result<T, CandyError>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(Deserializer::new(value))
+}
+
+/// The serde `Deserializer` behind `from_candy_value`.
+pub struct Deserializer {
+    value: CandyShared,
+}
+
+impl Deserializer {
+    pub fn new(value: CandyShared) -> Self {
+        Self { value }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, CandyError> for CandyShared {
+    type Deserializer = Deserializer;
+
+    fn into_deserializer(self) -> Deserializer {
+        Deserializer::new(self)
+    }
+}
+
+// The items of the collection variants, with the numbers and bytes of the typed ones wrapped.
+fn items(value: CandyShared) -> Result<Vec<CandyShared>, CandyShared> {
+    Ok(match value {
+        CandyShared::Array(val) => val,
+        CandyShared::Set(val) => val.into_iter().collect(),
+        CandyShared::Nats(val) => val.into_iter().map(CandyShared::Nat).collect(),
+        CandyShared::Ints(val) => val.into_iter().map(CandyShared::Int).collect(),
+        CandyShared::Floats(val) => val.into_iter().map(CandyShared::Float).collect(),
+        CandyShared::Blob(val) | CandyShared::Bytes(val) => {
+            val.into_iter().map(CandyShared::Nat8).collect()
+        }
+        _ => return Err(value),
+    })
+}
+
+fn visit_seq<'de, V>(items: Vec<CandyShared>, visitor: V) -> Result<V::Value, CandyError>
+where
+    V: Visitor<'de>,
+{
+    let mut seq = SeqDeserializer::new(items.into_iter());
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+fn visit_map<'de, I, K, V>(entries: I, visitor: V) -> Result<V::Value, CandyError>
+where
+    I: Iterator<Item = (K, CandyShared)>,
+    K: IntoDeserializer<'de, CandyError>,
+    V: Visitor<'de>,
+{
+    let mut map = MapDeserializer::new(entries);
+    let value = visitor.visit_map(&mut map)?;
+    map.end()?;
+    Ok(value)
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = CandyError;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            CandyShared::Int(val) => match (val.0.to_i64(), val.0.to_i128()) {
+                (Some(val), _) => visitor.visit_i64(val),
+                (None, Some(val)) => visitor.visit_i128(val),
+                (None, None) => Err(CandyError::Overflow),
+            },
+            CandyShared::Int8(val) => visitor.visit_i8(val),
+            CandyShared::Int16(val) => visitor.visit_i16(val),
+            CandyShared::Int32(val) => visitor.visit_i32(val),
+            CandyShared::Int64(val) => visitor.visit_i64(val),
+            CandyShared::Nat(val) => match (val.0.to_u64(), val.0.to_u128()) {
+                (Some(val), _) => visitor.visit_u64(val),
+                (None, Some(val)) => visitor.visit_u128(val),
+                (None, None) => Err(CandyError::Overflow),
+            },
+            CandyShared::Nat8(val) => visitor.visit_u8(val),
+            CandyShared::Nat16(val) => visitor.visit_u16(val),
+            CandyShared::Nat32(val) => visitor.visit_u32(val),
+            CandyShared::Nat64(val) => visitor.visit_u64(val),
+            CandyShared::Float(val) => visitor.visit_f64(val),
+            CandyShared::Text(val) => visitor.visit_string(val),
+            CandyShared::Bool(val) => visitor.visit_bool(val),
+            CandyShared::Blob(val) | CandyShared::Bytes(val) => visitor.visit_bytes(&val),
+            CandyShared::Principal(val) => visitor.visit_bytes(val.as_slice()),
+            CandyShared::Option(None) => visitor.visit_none(),
+            CandyShared::Option(Some(val)) => visitor.visit_some(Deserializer::new(*val)),
+            CandyShared::Map(val) => visit_map(val.into_iter(), visitor),
+            CandyShared::ValueMap(val) => visit_map(val.into_iter(), visitor),
+            CandyShared::Class(val) => visit_map(
+                val.into_iter()
+                    .map(|property| (property.name, property.value)),
+                visitor,
+            ),
+            value => match items(value) {
+                Ok(items) => visit_seq(items, visitor),
+                Err(value) => Err(CandyError::UnsupportedVariant(
+                    value.variant_name().to_string(),
+                )),
+            },
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            CandyShared::Option(None) => visitor.visit_none(),
+            CandyShared::Option(Some(val)) => visitor.visit_some(Deserializer::new(*val)),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            CandyShared::Option(None) => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        match items(self.value) {
+            Ok(items) => visit_seq(items, visitor),
+            Err(value) => Deserializer::new(value).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            CandyShared::Class(val) => visit_seq(
+                val.into_iter().map(|property| property.value).collect(),
+                visitor,
+            ),
+            _ => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            CandyShared::Text(label) => visitor.visit_enum(label.into_deserializer()),
+            CandyShared::Class(_) => {
+                let (label, value) = untag(self.value)?;
+                visitor.visit_enum(EnumDeserializer { label, value })
+            }
+            _ => Err(CandyError::UnsupportedVariant(
+                self.value.variant_name().to_string(),
+            )),
+        }
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        map struct identifier
+    }
+}
+
+// A variant tagged like `to_candy_value` tags them.
+struct EnumDeserializer {
+    label: String,
+    value: CandyShared,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = CandyError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantDeserializer), CandyError>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.label.into_deserializer())?;
+        Ok((variant, VariantDeserializer { value: self.value }))
+    }
+}
+
+struct VariantDeserializer {
+    value: CandyShared,
+}
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer {
+    type Error = CandyError;
+
+    fn unit_variant(self) -> Result<(), CandyError> {
+        match self.value {
+            CandyShared::Option(None) => Ok(()),
+            _ => Err(CandyError::UnsupportedVariant(
+                self.value.variant_name().to_string(),
+            )),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, CandyError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(Deserializer::new(self.value))
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_tuple(Deserializer::new(self.value), len, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, CandyError>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(Deserializer::new(self.value), visitor)
+    }
+}
@@ -0,0 +1,86 @@
+//! Support for the code generated by the `ic_candy_derive` macros, not meant to be used directly.
+
+use crate::idl::{VARIANT_TAG, VARIANT_VALUE};
+use crate::types::{CandyError, PropertyShared};
+use crate::value::{CandyShared, FromCandyValue};
+
+/// Tags `value` as the enum variant `label`, in the shape `idl::from_idl_value` gives Candid variants.
+pub fn variant(label: &str, value: CandyShared) -> CandyShared {
+    CandyShared::Class(vec![
+        PropertyShared {
+            name: VARIANT_TAG.to_string(),
+            value: CandyShared::Text(label.to_string()),
+            immutable: true,
+        },
+        PropertyShared {
+            name: VARIANT_VALUE.to_string(),
+            value,
+            immutable: true,
+        },
+    ])
+}
+
+/// Splits a value tagged by `variant` into its label and value.
+pub fn untag(value: CandyShared) -> Result<(String, CandyShared), CandyError> {
+    let mut properties = class(value)?;
+    let label = match take(&mut properties, VARIANT_TAG)? {
+        CandyShared::Text(label) => label,
+        other => {
+            return Err(CandyError::InvalidField(
+                VARIANT_TAG.to_string(),
+                Box::new(CandyError::UnsupportedVariant(
+                    other.variant_name().to_string(),
+                )),
+            ))
+        }
+    };
+    Ok((label, take(&mut properties, VARIANT_VALUE)?))
+}
+
+pub fn unknown_variant(label: &str) -> CandyError {
+    CandyError::InvalidEncoding(format!("unknown variant {}", label))
+}
+
+pub fn class(value: CandyShared) -> Result<Vec<PropertyShared>, CandyError> {
+    match value {
+        CandyShared::Class(properties) => Ok(properties),
+        _ => Err(CandyError::UnsupportedVariant(
+            value.variant_name().to_string(),
+        )),
+    }
+}
+
+/// Removes the property `name` from `properties` and converts its value.
+pub fn field<T>(properties: &mut Vec<PropertyShared>, name: &str) -> Result<T, CandyError>
+where
+    T: FromCandyValue,
+{
+    T::from_candy(take(properties, name)?)
+        .map_err(|error| CandyError::InvalidField(name.to_string(), Box::new(error)))
+}
+
+/// Converts the value of the newtype or newtype variant `name`.
+pub fn value<T>(name: &str, value: CandyShared) -> Result<T, CandyError>
+where
+    T: FromCandyValue,
+{
+    T::from_candy(value)
+        .map_err(|error| CandyError::InvalidField(name.to_string(), Box::new(error)))
+}
+
+fn take(properties: &mut Vec<PropertyShared>, name: &str) -> Result<CandyShared, CandyError> {
+    let index = properties
+        .iter()
+        .position(|property| property.name == name)
+        .ok_or_else(|| CandyError::MissingField(name.to_string()))?;
+    Ok(properties.remove(index).value)
+}
+
+pub fn unit(value: CandyShared) -> Result<(), CandyError> {
+    match value {
+        CandyShared::Option(None) => Ok(()),
+        _ => Err(CandyError::UnsupportedVariant(
+            value.variant_name().to_string(),
+        )),
+    }
+}
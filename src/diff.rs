@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::path::{CandyPath, PathSegment};
+use crate::value::CandyShared;
+
+/// The kind of a single `Change` reported by `diff`.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum ChangeKind {
+    /// The value only exists in the new tree.
+    Added(CandyShared),
+    /// The value only exists in the old tree.
+    Removed(CandyShared),
+    /// The value exists in both trees but differs.
+    Modified { old: CandyShared, new: CandyShared },
+    /// The `immutable` flag of a `Class` property changed.
+    ImmutableChanged { old: bool, new: bool },
+}
+
+/// A difference between two `CandyShared` trees at the given path.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub struct Change {
+    pub path: CandyPath,
+    pub kind: ChangeKind,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ChangeKind::Added(value) => write!(f, "+ {}: {}", self.path, value),
+            ChangeKind::Removed(value) => write!(f, "- {}: {}", self.path, value),
+            ChangeKind::Modified { old, new } => write!(f, "~ {}: {} -> {}", self.path, old, new),
+            ChangeKind::ImmutableChanged { old, new } => {
+                write!(f, "! {}: immutable {} -> {}", self.path, old, new)
+            }
+        }
+    }
+}
+
+/// Computes the structural differences between `old` and `new`.
+///
+/// `Class` properties, `Array` indices, `Map`/`ValueMap` keys and `Set` members are compared one by one,
+/// any other differing values are reported as `Modified`. `Option(Some(..))` values are compared by their content,
+/// so that every reported path can be used with `CandyShared::get`. Changes are reported in a deterministic order.
+///
+/// ```
+/// use ic_candy::diff::diff;
+/// use ic_candy::types::PropertyShared;
+/// use ic_candy::value::ToCandyValue;
+///
+/// let old = vec![PropertyShared { name: "name".to_string(), value: "candy".to_candy(), immutable: false }].to_candy();
+/// let new = vec![PropertyShared { name: "name".to_string(), value: "sugar".to_candy(), immutable: true }].to_candy();
+/// let changes: Vec<String> = diff(&old, &new).iter().map(|c| c.to_string()).collect();
+/// assert_eq!(changes, vec!["! name: immutable false -> true", "~ name: candy -> sugar"]);
+/// ```
+pub fn diff(old: &CandyShared, new: &CandyShared) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_into(&CandyPath::root(), old, new, &mut changes);
+    changes
+}
+
+fn push(changes: &mut Vec<Change>, path: CandyPath, kind: ChangeKind) {
+    changes.push(Change { path, kind });
+}
+
+fn diff_into(path: &CandyPath, old: &CandyShared, new: &CandyShared, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (CandyShared::Option(Some(old)), CandyShared::Option(Some(new))) => {
+            diff_into(path, old, new, changes)
+        }
+        (CandyShared::Class(old), CandyShared::Class(new)) => {
+            for old_prop in old {
+                let prop_path = path.join(PathSegment::Key(old_prop.name.clone()));
+                match new.iter().find(|p| p.name == old_prop.name) {
+                    Some(new_prop) => {
+                        if old_prop.immutable != new_prop.immutable {
+                            push(
+                                changes,
+                                prop_path.clone(),
+                                ChangeKind::ImmutableChanged {
+                                    old: old_prop.immutable,
+                                    new: new_prop.immutable,
+                                },
+                            );
+                        }
+                        diff_into(&prop_path, &old_prop.value, &new_prop.value, changes);
+                    }
+                    None => push(
+                        changes,
+                        prop_path,
+                        ChangeKind::Removed(old_prop.value.clone()),
+                    ),
+                }
+            }
+            for new_prop in new {
+                if !old.iter().any(|p| p.name == new_prop.name) {
+                    push(
+                        changes,
+                        path.join(PathSegment::Key(new_prop.name.clone())),
+                        ChangeKind::Added(new_prop.value.clone()),
+                    );
+                }
+            }
+        }
+        (CandyShared::Array(old), CandyShared::Array(new)) => {
+            for (index, (old, new)) in old.iter().zip(new.iter()).enumerate() {
+                diff_into(
+                    &path.join(PathSegment::Index(index as u64)),
+                    old,
+                    new,
+                    changes,
+                );
+            }
+            for (index, value) in old.iter().enumerate().skip(new.len()) {
+                push(
+                    changes,
+                    path.join(PathSegment::Index(index as u64)),
+                    ChangeKind::Removed(value.clone()),
+                );
+            }
+            for (index, value) in new.iter().enumerate().skip(old.len()) {
+                push(
+                    changes,
+                    path.join(PathSegment::Index(index as u64)),
+                    ChangeKind::Added(value.clone()),
+                );
+            }
+        }
+        (CandyShared::Map(old), CandyShared::Map(new)) => {
+            diff_maps(path, old, new, |key| PathSegment::Key(key.clone()), changes)
+        }
+        (CandyShared::ValueMap(old), CandyShared::ValueMap(new)) => diff_maps(
+            path,
+            old,
+            new,
+            |key| match key {
+                CandyShared::Text(key) => PathSegment::Key(key.clone()),
+                key => PathSegment::Value(key.clone()),
+            },
+            changes,
+        ),
+        (CandyShared::Set(old), CandyShared::Set(new)) => {
+            for member in old.difference(new) {
+                push(
+                    changes,
+                    path.join(PathSegment::Value(member.clone())),
+                    ChangeKind::Removed(member.clone()),
+                );
+            }
+            for member in new.difference(old) {
+                push(
+                    changes,
+                    path.join(PathSegment::Value(member.clone())),
+                    ChangeKind::Added(member.clone()),
+                );
+            }
+        }
+        (old, new) if old == new => {}
+        (old, new) => push(
+            changes,
+            path.clone(),
+            ChangeKind::Modified {
+                old: old.clone(),
+                new: new.clone(),
+            },
+        ),
+    }
+}
+
+fn diff_maps<K: Ord>(
+    path: &CandyPath,
+    old: &BTreeMap<K, CandyShared>,
+    new: &BTreeMap<K, CandyShared>,
+    segment: impl Fn(&K) -> PathSegment,
+    changes: &mut Vec<Change>,
+) {
+    let keys: BTreeSet<&K> = old.keys().chain(new.keys()).collect();
+    for key in keys {
+        let key_path = path.join(segment(key));
+        match (old.get(key), new.get(key)) {
+            (Some(old), Some(new)) => diff_into(&key_path, old, new, changes),
+            (Some(old), None) => push(changes, key_path, ChangeKind::Removed(old.clone())),
+            (None, Some(new)) => push(changes, key_path, ChangeKind::Added(new.clone())),
+            (None, None) => {}
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use num_bigint::{BigInt, BigUint};
+use num_traits::{ToPrimitive, Zero};
+use sha2::{Digest, Sha256};
+
+use crate::value::CandyShared;
+
+/// Computes the ICRC-3 representation-independent hash of a value.
+///
+/// The value is hashed as the `Icrc3Value` it converts to by default, see `icrc::Icrc3Value`, except for
+/// `Bool`, which is hashed as a blob rather than as the `Nat` it converts to:
+///
+/// * `Nat` and `Nat8`..`Nat64` as `Nat`, the SHA-256 of their LEB128 encoding,
+/// * `Int` and `Int8`..`Int64` as `Int`, the SHA-256 of their signed LEB128 encoding,
+/// * `Text` as `Text`, the SHA-256 of its UTF-8 bytes,
+/// * `Blob`, `Bytes` and `Principal` as `Blob`, the SHA-256 of the bytes,
+///   `Bool` as the one byte blob `1` or `0`, which hashes like the `Nat` `1` or `0`,
+/// * `Float` as the `Text` of its shortest round-trip representation, e.g. `"1.5"` or `"NaN"`,
+/// * `Array`, `Nats`, `Ints`, `Floats` and `Set` as `Array`, the SHA-256 of the concatenated item hashes,
+///   `Option` as an empty or one item `Array` and `ValueMap` as an `Array` of `[key, value]` arrays,
+/// * `Class` and `Map` as `Map`, the SHA-256 of the concatenated, bytewise sorted `hash(key) ++ hash(value)`
+///   pairs. The `immutable` flag of `Class` properties is not part of the hash, and of properties with the
+///   same name only the last one is.
+///
+/// ```
+/// use ic_candy::hashing::hash_sha256;
+/// use ic_candy::value::ToCandyValue;
+///
+/// assert_eq!(
+///     hex::encode(hash_sha256(&42_u8.to_candy())),
+///     "684888c0ebb17f374298b65ee2807526c066094c701bcc7ebbe1c1095f494fc1"
+/// );
+/// // The hash does not depend on the integer width.
+/// assert_eq!(hash_sha256(&42_u8.to_candy()), hash_sha256(&42_u128.to_candy()));
+/// ```
+pub fn hash_sha256(value: &CandyShared) -> [u8; 32] {
+    match value {
+        CandyShared::Nat(val) => hash_nat(&val.0),
+        CandyShared::Nat8(val) => hash_nat(&BigUint::from(*val)),
+        CandyShared::Nat16(val) => hash_nat(&BigUint::from(*val)),
+        CandyShared::Nat32(val) => hash_nat(&BigUint::from(*val)),
+        CandyShared::Nat64(val) => hash_nat(&BigUint::from(*val)),
+        CandyShared::Int(val) => hash_int(&val.0),
+        CandyShared::Int8(val) => hash_int(&BigInt::from(*val)),
+        CandyShared::Int16(val) => hash_int(&BigInt::from(*val)),
+        CandyShared::Int32(val) => hash_int(&BigInt::from(*val)),
+        CandyShared::Int64(val) => hash_int(&BigInt::from(*val)),
+        CandyShared::Float(val) => hash_bytes(val.to_string().as_bytes()),
+        CandyShared::Text(val) => hash_bytes(val.as_bytes()),
+        CandyShared::Bool(val) => hash_bytes(&[u8::from(*val)]),
+        CandyShared::Blob(val) | CandyShared::Bytes(val) => hash_bytes(val),
+        CandyShared::Principal(val) => hash_bytes(val.as_slice()),
+        // Like the `Map` it converts to, a repeated property name keeps its last value.
+        CandyShared::Class(val) => hash_map(
+            val.iter()
+                .map(|p| (p.name.as_str(), hash_sha256(&p.value)))
+                .collect::<BTreeMap<_, _>>()
+                .into_iter(),
+        ),
+        CandyShared::Option(val) => hash_array(val.iter().map(|val| hash_sha256(val))),
+        CandyShared::Array(val) => hash_array(val.iter().map(hash_sha256)),
+        CandyShared::Nats(val) => hash_array(val.iter().map(|val| hash_nat(&val.0))),
+        CandyShared::Ints(val) => hash_array(val.iter().map(|val| hash_int(&val.0))),
+        CandyShared::Floats(val) => {
+            hash_array(val.iter().map(|val| hash_bytes(val.to_string().as_bytes())))
+        }
+        CandyShared::Map(val) => hash_map(
+            val.iter()
+                .map(|(key, val)| (key.as_str(), hash_sha256(val))),
+        ),
+        CandyShared::ValueMap(val) => hash_array(
+            val.iter()
+                .map(|(key, val)| hash_array([hash_sha256(key), hash_sha256(val)].into_iter())),
+        ),
+        CandyShared::Set(val) => hash_array(val.iter().map(hash_sha256)),
+    }
+}
+
+pub(crate) fn hash_bytes(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+pub(crate) fn hash_nat(nat: &BigUint) -> [u8; 32] {
+    hash_bytes(&leb128(nat))
+}
+
+pub(crate) fn hash_int(int: &BigInt) -> [u8; 32] {
+    hash_bytes(&sleb128(int))
+}
+
+pub(crate) fn hash_array(items: impl Iterator<Item = [u8; 32]>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for item in items {
+        hasher.update(item);
+    }
+    hasher.finalize().into()
+}
+
+pub(crate) fn hash_map<'a>(entries: impl Iterator<Item = (&'a str, [u8; 32])>) -> [u8; 32] {
+    let mut pairs: Vec<[u8; 64]> = entries
+        .map(|(key, value)| {
+            let mut pair = [0; 64];
+            pair[..32].copy_from_slice(&hash_bytes(key.as_bytes()));
+            pair[32..].copy_from_slice(&value);
+            pair
+        })
+        .collect();
+    pairs.sort();
+    let mut hasher = Sha256::new();
+    for pair in pairs {
+        hasher.update(pair);
+    }
+    hasher.finalize().into()
+}
+
+fn leb128(nat: &BigUint) -> Vec<u8> {
+    let mut bytes = nat.to_radix_le(128);
+    let last = bytes.len() - 1;
+    for byte in &mut bytes[..last] {
+        *byte |= 0x80;
+    }
+    bytes
+}
+
+fn sleb128(int: &BigInt) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut int = int.clone();
+    loop {
+        // `&` and `>>` work on the two's complement, `>>` rounding towards negative infinity.
+        let byte = (&int & BigInt::from(0x7f))
+            .to_u8()
+            .expect("masked to 7 bits");
+        int >>= 7;
+        let sign_bit_clear = byte & 0x40 == 0;
+        if (int.is_zero() && sign_bit_clear) || (int == BigInt::from(-1) && !sign_bit_clear) {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
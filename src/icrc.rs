@@ -0,0 +1,259 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::hashing;
+use crate::types::{CandyError, PropertyShared};
+use crate::value::CandyShared;
+
+/// The generic `Value` of ICRC-3 blocks and ICRC-7 metadata.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum Icrc3Value {
+    Nat(candid::Nat),
+    Int(candid::Int),
+    Text(String),
+    Blob(Vec<u8>),
+    Array(Vec<Icrc3Value>),
+    Map(Vec<(String, Icrc3Value)>),
+}
+
+/// How `Icrc3Value::from_candy` encodes `Bool` values, which ICRC-3 has no variant for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BoolEncoding {
+    /// `Nat` `1` or `0`.
+    #[default]
+    Nat,
+    /// `Text` `"true"` or `"false"`.
+    Text,
+    /// The one byte `Blob` `1` or `0`.
+    Blob,
+}
+
+/// Options for `Icrc3Value::from_candy`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Icrc3Options {
+    pub bool_encoding: BoolEncoding,
+}
+
+impl Icrc3Value {
+    /// Converts a `CandyShared` into the ICRC-3 `Value` it is closest to:
+    ///
+    /// * `Nat8`..`Nat64` into `Nat`, `Int8`..`Int64` into `Int` and `Bool` as configured,
+    /// * `Bytes` and `Principal` into `Blob`, `Float` into the `Text` of its shortest round-trip representation,
+    /// * `Class` into `Map`, dropping the `immutable` flags and keeping the last of properties with the same
+    ///   name, and `Map` into `Map`,
+    /// * `Option` into an empty or one item `Array`, `ValueMap` into an `Array` of `[key, value]` arrays,
+    ///   `Nats`, `Ints`, `Floats` and `Set` into `Array`.
+    ///
+    /// Use `lossy_variants` to find out whether converting back restores the value.
+    ///
+    /// ```
+    /// use ic_candy::icrc::{BoolEncoding, Icrc3Options, Icrc3Value};
+    /// use ic_candy::value::ToCandyValue;
+    ///
+    /// let options = Icrc3Options { bool_encoding: BoolEncoding::Text };
+    /// assert_eq!(Icrc3Value::from_candy(true.to_candy(), &options), Icrc3Value::Text("true".to_string()));
+    /// assert_eq!(Icrc3Value::from(true.to_candy()), Icrc3Value::Nat(1_u8.into()));
+    /// ```
+    pub fn from_candy(value: CandyShared, options: &Icrc3Options) -> Self {
+        let convert = |value: CandyShared| Self::from_candy(value, options);
+        match value {
+            CandyShared::Nat(val) => Icrc3Value::Nat(val),
+            CandyShared::Nat8(val) => Icrc3Value::Nat(val.into()),
+            CandyShared::Nat16(val) => Icrc3Value::Nat(val.into()),
+            CandyShared::Nat32(val) => Icrc3Value::Nat(val.into()),
+            CandyShared::Nat64(val) => Icrc3Value::Nat(val.into()),
+            CandyShared::Int(val) => Icrc3Value::Int(val),
+            CandyShared::Int8(val) => Icrc3Value::Int(val.into()),
+            CandyShared::Int16(val) => Icrc3Value::Int(val.into()),
+            CandyShared::Int32(val) => Icrc3Value::Int(val.into()),
+            CandyShared::Int64(val) => Icrc3Value::Int(val.into()),
+            CandyShared::Float(val) => Icrc3Value::Text(val.to_string()),
+            CandyShared::Text(val) => Icrc3Value::Text(val),
+            CandyShared::Bool(val) => match options.bool_encoding {
+                BoolEncoding::Nat => Icrc3Value::Nat(u8::from(val).into()),
+                BoolEncoding::Text => Icrc3Value::Text(val.to_string()),
+                BoolEncoding::Blob => Icrc3Value::Blob(vec![u8::from(val)]),
+            },
+            CandyShared::Blob(val) | CandyShared::Bytes(val) => Icrc3Value::Blob(val),
+            CandyShared::Principal(val) => Icrc3Value::Blob(val.as_slice().to_vec()),
+            CandyShared::Class(val) => {
+                // ICRC-3 map keys are unique, so a repeated property name keeps its last value like `to_map`.
+                let mut positions = HashMap::<String, usize>::new();
+                let mut entries: Vec<(String, Icrc3Value)> = Vec::with_capacity(val.len());
+                for prop in val {
+                    let value = convert(prop.value);
+                    match positions.get(&prop.name) {
+                        Some(&i) => entries[i].1 = value,
+                        None => {
+                            positions.insert(prop.name.clone(), entries.len());
+                            entries.push((prop.name, value));
+                        }
+                    }
+                }
+                Icrc3Value::Map(entries)
+            }
+            CandyShared::Option(val) => {
+                Icrc3Value::Array(val.into_iter().map(|val| convert(*val)).collect())
+            }
+            CandyShared::Array(val) => Icrc3Value::Array(val.into_iter().map(convert).collect()),
+            CandyShared::Nats(val) => {
+                Icrc3Value::Array(val.into_iter().map(Icrc3Value::Nat).collect())
+            }
+            CandyShared::Ints(val) => {
+                Icrc3Value::Array(val.into_iter().map(Icrc3Value::Int).collect())
+            }
+            CandyShared::Floats(val) => Icrc3Value::Array(
+                val.into_iter()
+                    .map(|val| Icrc3Value::Text(val.to_string()))
+                    .collect(),
+            ),
+            CandyShared::Map(val) => Icrc3Value::Map(
+                val.into_iter()
+                    .map(|(key, val)| (key, convert(val)))
+                    .collect(),
+            ),
+            CandyShared::ValueMap(val) => Icrc3Value::Array(
+                val.into_iter()
+                    .map(|(key, val)| Icrc3Value::Array(vec![convert(key), convert(val)]))
+                    .collect(),
+            ),
+            CandyShared::Set(val) => Icrc3Value::Array(val.into_iter().map(convert).collect()),
+        }
+    }
+
+    /// Computes the ICRC-3 representation-independent hash of the value.
+    ///
+    /// It equals `hashing::hash_sha256` of the `CandyShared` the value was converted from, except for
+    /// `Bool`, which `hashing::hash_sha256` hashes as the one byte blob `1` or `0` whatever the
+    /// `BoolEncoding`. That is the hash of `BoolEncoding::Nat` and `BoolEncoding::Blob` too, as LEB128
+    /// encodes `1` and `0` as that byte, but not of `BoolEncoding::Text`.
+    ///
+    /// ```
+    /// use ic_candy::icrc::Icrc3Value;
+    /// use ic_candy::value::ToCandyValue;
+    ///
+    /// let value = vec![1_u8.to_candy(), "candy".to_candy()].to_candy();
+    /// assert_eq!(Icrc3Value::from(value.clone()).hash_sha256(), value.hash_sha256());
+    /// ```
+    pub fn hash_sha256(&self) -> [u8; 32] {
+        match self {
+            Icrc3Value::Nat(val) => hashing::hash_nat(&val.0),
+            Icrc3Value::Int(val) => hashing::hash_int(&val.0),
+            Icrc3Value::Text(val) => hashing::hash_bytes(val.as_bytes()),
+            Icrc3Value::Blob(val) => hashing::hash_bytes(val),
+            Icrc3Value::Array(val) => hashing::hash_array(val.iter().map(Icrc3Value::hash_sha256)),
+            Icrc3Value::Map(val) => hashing::hash_map(
+                val.iter()
+                    .map(|(key, val)| (key.as_str(), val.hash_sha256())),
+            ),
+        }
+    }
+}
+
+impl From<CandyShared> for Icrc3Value {
+    fn from(value: CandyShared) -> Self {
+        Icrc3Value::from_candy(value, &Icrc3Options::default())
+    }
+}
+
+/// Converts `Nat`, `Int`, `Text`, `Blob` and `Array` into the same `CandyShared` variants
+/// and `Map` into a `Class` of mutable properties.
+/// Fails with `CandyError::InvalidEncoding` if a `Map` has duplicate keys, which ICRC-3 does not allow.
+impl TryFrom<Icrc3Value> for CandyShared {
+    type Error = CandyError;
+
+    fn try_from(value: Icrc3Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Icrc3Value::Nat(val) => CandyShared::Nat(val),
+            Icrc3Value::Int(val) => CandyShared::Int(val),
+            Icrc3Value::Text(val) => CandyShared::Text(val),
+            Icrc3Value::Blob(val) => CandyShared::Blob(val),
+            Icrc3Value::Array(val) => CandyShared::Array(
+                val.into_iter()
+                    .map(CandyShared::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Icrc3Value::Map(val) => {
+                let mut names = HashSet::new();
+                CandyShared::Class(
+                    val.into_iter()
+                        .map(|(name, value)| {
+                            if !names.insert(name.clone()) {
+                                return Err(CandyError::InvalidEncoding(format!(
+                                    "duplicate ICRC-3 map key {}",
+                                    name
+                                )));
+                            }
+                            Ok(PropertyShared {
+                                name,
+                                value: CandyShared::try_from(value)?,
+                                immutable: false,
+                            })
+                        })
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+        })
+    }
+}
+
+/// Returns the names of the variants in `value`, nested ones included, that converting to `Icrc3Value`
+/// and back does not restore: everything but `Nat`, `Int`, `Text`, `Blob`, `Array` and `Class` without
+/// immutable properties or duplicate names, of which only the last is kept. An empty result means the
+/// round trip is lossless.
+///
+/// ```
+/// use ic_candy::icrc::lossy_variants;
+/// use ic_candy::value::ToCandyValue;
+///
+/// let value = vec![1_u128.to_candy(), 2_u8.to_candy(), true.to_candy()].to_candy();
+/// assert_eq!(lossy_variants(&value).into_iter().collect::<Vec<_>>(), vec!["Bool", "Nat8"]);
+/// ```
+pub fn lossy_variants(value: &CandyShared) -> BTreeSet<&'static str> {
+    let mut lossy = BTreeSet::new();
+    collect_lossy(value, &mut lossy);
+    lossy
+}
+
+fn collect_lossy(value: &CandyShared, lossy: &mut BTreeSet<&'static str>) {
+    match value {
+        CandyShared::Nat(_) | CandyShared::Int(_) | CandyShared::Text(_) | CandyShared::Blob(_) => {
+        }
+        CandyShared::Array(val) => val.iter().for_each(|val| collect_lossy(val, lossy)),
+        CandyShared::Class(val) => {
+            let mut names = HashSet::new();
+            if val
+                .iter()
+                .any(|prop| prop.immutable || !names.insert(&prop.name))
+            {
+                lossy.insert("Class");
+            }
+            val.iter()
+                .for_each(|prop| collect_lossy(&prop.value, lossy));
+        }
+        CandyShared::Option(val) => {
+            lossy.insert("Option");
+            val.iter().for_each(|val| collect_lossy(val, lossy));
+        }
+        CandyShared::Map(val) => {
+            lossy.insert("Map");
+            val.values().for_each(|val| collect_lossy(val, lossy));
+        }
+        CandyShared::ValueMap(val) => {
+            lossy.insert("ValueMap");
+            for (key, val) in val {
+                collect_lossy(key, lossy);
+                collect_lossy(val, lossy);
+            }
+        }
+        CandyShared::Set(val) => {
+            lossy.insert("Set");
+            val.iter().for_each(|val| collect_lossy(val, lossy));
+        }
+        value => {
+            lossy.insert(value.variant_name());
+        }
+    }
+}
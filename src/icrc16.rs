@@ -0,0 +1,195 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{CandyError, PropertyShared};
+use crate::value::CandyShared;
+
+/// The ICRC-16 `CandyShared` Candid type.
+///
+/// It has the same variants as this crate's `CandyShared`, but keeps `Map` and `ValueMap` as vectors of
+/// key-value tuples and `Set` as a vector, in the order they were sent.
+#[derive(Clone, Debug, PartialEq, CandidType, Serialize, Deserialize)]
+pub enum Icrc16Value {
+    Int(candid::Int),
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    Ints(Vec<candid::Int>),
+    Nat(candid::Nat),
+    Nat8(u8),
+    Nat16(u16),
+    Nat32(u32),
+    Nat64(u64),
+    Float(f64),
+    Text(String),
+    Bool(bool),
+    Blob(Vec<u8>),
+    Bytes(Vec<u8>),
+    Class(Vec<Icrc16Property>),
+    Principal(Principal),
+    Option(Option<Box<Icrc16Value>>),
+    Array(Vec<Icrc16Value>),
+    Nats(Vec<candid::Nat>),
+    Floats(Vec<f64>),
+    Map(Vec<(String, Icrc16Value)>),
+    ValueMap(Vec<(Icrc16Value, Icrc16Value)>),
+    Set(Vec<Icrc16Value>),
+}
+
+/// The ICRC-16 `PropertyShared` Candid type.
+#[derive(Clone, Debug, PartialEq, CandidType, Serialize, Deserialize)]
+pub struct Icrc16Property {
+    pub name: String,
+    pub value: Icrc16Value,
+    pub immutable: bool,
+}
+
+/// Converts every variant into its ICRC-16 namesake; `Map`, `ValueMap` and `Set` come out sorted.
+///
+/// ```
+/// use ic_candy::icrc16::Icrc16Value;
+/// use ic_candy::value::{CandyShared, ToCandyValue};
+///
+/// let value = vec![1_u8.to_candy(), "candy".to_candy()].to_candy();
+/// let icrc16 = Icrc16Value::from(value.clone());
+/// assert_eq!(CandyShared::try_from(icrc16), Ok(value));
+/// ```
+impl From<CandyShared> for Icrc16Value {
+    fn from(value: CandyShared) -> Self {
+        match value {
+            CandyShared::Int(val) => Icrc16Value::Int(val),
+            CandyShared::Int8(val) => Icrc16Value::Int8(val),
+            CandyShared::Int16(val) => Icrc16Value::Int16(val),
+            CandyShared::Int32(val) => Icrc16Value::Int32(val),
+            CandyShared::Int64(val) => Icrc16Value::Int64(val),
+            CandyShared::Ints(val) => Icrc16Value::Ints(val),
+            CandyShared::Nat(val) => Icrc16Value::Nat(val),
+            CandyShared::Nat8(val) => Icrc16Value::Nat8(val),
+            CandyShared::Nat16(val) => Icrc16Value::Nat16(val),
+            CandyShared::Nat32(val) => Icrc16Value::Nat32(val),
+            CandyShared::Nat64(val) => Icrc16Value::Nat64(val),
+            CandyShared::Float(val) => Icrc16Value::Float(val),
+            CandyShared::Text(val) => Icrc16Value::Text(val),
+            CandyShared::Bool(val) => Icrc16Value::Bool(val),
+            CandyShared::Blob(val) => Icrc16Value::Blob(val),
+            CandyShared::Bytes(val) => Icrc16Value::Bytes(val),
+            CandyShared::Class(val) => Icrc16Value::Class(
+                val.into_iter()
+                    .map(|prop| Icrc16Property {
+                        name: prop.name,
+                        value: prop.value.into(),
+                        immutable: prop.immutable,
+                    })
+                    .collect(),
+            ),
+            CandyShared::Principal(val) => Icrc16Value::Principal(val),
+            CandyShared::Option(val) => Icrc16Value::Option(val.map(|val| Box::new((*val).into()))),
+            CandyShared::Array(val) => {
+                Icrc16Value::Array(val.into_iter().map(Into::into).collect())
+            }
+            CandyShared::Nats(val) => Icrc16Value::Nats(val),
+            CandyShared::Floats(val) => Icrc16Value::Floats(val),
+            CandyShared::Map(val) => Icrc16Value::Map(
+                val.into_iter()
+                    .map(|(key, val)| (key, val.into()))
+                    .collect(),
+            ),
+            CandyShared::ValueMap(val) => Icrc16Value::ValueMap(
+                val.into_iter()
+                    .map(|(key, val)| (key.into(), val.into()))
+                    .collect(),
+            ),
+            CandyShared::Set(val) => Icrc16Value::Set(val.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+/// Converts every variant into its `CandyShared` namesake, sorting `Map`, `ValueMap` and `Set`.
+/// Fails with `CandyError::InvalidEncoding` on a duplicate `Map` or `ValueMap` key or `Set` member,
+/// which the sorted collections could only keep by dropping data.
+impl TryFrom<Icrc16Value> for CandyShared {
+    type Error = CandyError;
+
+    fn try_from(value: Icrc16Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Icrc16Value::Int(val) => CandyShared::Int(val),
+            Icrc16Value::Int8(val) => CandyShared::Int8(val),
+            Icrc16Value::Int16(val) => CandyShared::Int16(val),
+            Icrc16Value::Int32(val) => CandyShared::Int32(val),
+            Icrc16Value::Int64(val) => CandyShared::Int64(val),
+            Icrc16Value::Ints(val) => CandyShared::Ints(val),
+            Icrc16Value::Nat(val) => CandyShared::Nat(val),
+            Icrc16Value::Nat8(val) => CandyShared::Nat8(val),
+            Icrc16Value::Nat16(val) => CandyShared::Nat16(val),
+            Icrc16Value::Nat32(val) => CandyShared::Nat32(val),
+            Icrc16Value::Nat64(val) => CandyShared::Nat64(val),
+            Icrc16Value::Float(val) => CandyShared::Float(val),
+            Icrc16Value::Text(val) => CandyShared::Text(val),
+            Icrc16Value::Bool(val) => CandyShared::Bool(val),
+            Icrc16Value::Blob(val) => CandyShared::Blob(val),
+            Icrc16Value::Bytes(val) => CandyShared::Bytes(val),
+            Icrc16Value::Class(val) => CandyShared::Class(
+                val.into_iter()
+                    .map(|prop| {
+                        Ok(PropertyShared {
+                            name: prop.name,
+                            value: prop.value.try_into()?,
+                            immutable: prop.immutable,
+                        })
+                    })
+                    .collect::<Result<_, CandyError>>()?,
+            ),
+            Icrc16Value::Principal(val) => CandyShared::Principal(val),
+            Icrc16Value::Option(val) => CandyShared::Option(match val {
+                Some(val) => Some(Box::new((*val).try_into()?)),
+                None => None,
+            }),
+            Icrc16Value::Array(val) => CandyShared::Array(
+                val.into_iter()
+                    .map(CandyShared::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Icrc16Value::Nats(val) => CandyShared::Nats(val),
+            Icrc16Value::Floats(val) => CandyShared::Floats(val),
+            Icrc16Value::Map(val) => {
+                let mut map = BTreeMap::new();
+                for (key, val) in val {
+                    if map.contains_key(&key) {
+                        return Err(duplicate("Map key", &key));
+                    }
+                    map.insert(key, val.try_into()?);
+                }
+                CandyShared::Map(map)
+            }
+            Icrc16Value::ValueMap(val) => {
+                let mut map = BTreeMap::new();
+                for (key, val) in val {
+                    let key = CandyShared::try_from(key)?;
+                    if map.contains_key(&key) {
+                        return Err(duplicate("ValueMap key", &key));
+                    }
+                    map.insert(key, val.try_into()?);
+                }
+                CandyShared::ValueMap(map)
+            }
+            Icrc16Value::Set(val) => {
+                let mut set = BTreeSet::new();
+                for val in val {
+                    let val = CandyShared::try_from(val)?;
+                    if set.contains(&val) {
+                        return Err(duplicate("Set member", &val));
+                    }
+                    set.insert(val);
+                }
+                CandyShared::Set(set)
+            }
+        })
+    }
+}
+
+fn duplicate(what: &str, value: &dyn std::fmt::Display) -> CandyError {
+    CandyError::InvalidEncoding(format!("duplicate ICRC-16 {} {}", what, value))
+}
@@ -0,0 +1,336 @@
+use std::fmt::Display;
+
+use candid::types::value::{IDLField, VariantValue};
+use candid::types::Label;
+use candid::{CandidType, IDLValue};
+use serde::{Deserialize, Serialize};
+
+use crate::types::PropertyShared;
+use crate::value::CandyShared;
+
+/// The name of the property holding the label of a Candid variant, see `from_idl_value`.
+pub const VARIANT_TAG: &str = "variant";
+/// The name of the property holding the value of a Candid variant, see `from_idl_value`.
+pub const VARIANT_VALUE: &str = "value";
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum IdlError {
+    /// A Candid value candy has no variant for: a function or service reference.
+    Unrepresentable(String),
+    /// An untyped Candid number that is neither an integer nor a float.
+    InvalidNumber(String),
+    /// Two `Class` properties whose names map to the same Candid field id.
+    DuplicateLabel(String),
+}
+
+impl Display for IdlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IdlError::Unrepresentable(value) => {
+                write!(f, "unrepresentable Candid value: {}", value)
+            }
+            IdlError::InvalidNumber(number) => write!(f, "invalid Candid number: {}", number),
+            IdlError::DuplicateLabel(label) => write!(f, "duplicate Candid field: {}", label),
+        }
+    }
+}
+
+impl std::error::Error for IdlError {}
+
+/// Converts a dynamically typed Candid value into a `CandyShared`:
+///
+/// * numbers, `text`, `bool`, `blob` and `principal` into their namesakes, `float32` into `Float`,
+///   untyped numbers into `Nat`, `Int` or `Float`, whichever parses first,
+/// * `null`, `reserved` and an empty `opt` into `Option(None)`,
+/// * a `vec` of `nat8` values into `Bytes`, `vec nat`, `vec int` and `vec float64` into `Nats`, `Ints`
+///   and `Floats`, any other `vec` into `Array`,
+/// * a `vec nat8` decoded from Candid bytes into `Blob`, since the decoder reads it as a `blob`,
+/// * `record` into a `Class` of mutable properties named by the field labels, numeric ids included;
+///   Candid values decoded without their type only carry the ids, e.g. `"1136829802"` for `from`,
+/// * `variant` into a `Class` of the two immutable properties `VARIANT_TAG`, the label as `Text`, and
+///   `VARIANT_VALUE`.
+///
+/// `to_idl_value` converts the result back into an equal `IDLValue`, except that an empty `opt` and
+/// `reserved` become `null` and `float32` becomes `float64`. Function and service references fail with
+/// `IdlError::Unrepresentable`.
+///
+/// ```
+/// use candid::types::value::IDLField;
+/// use candid::types::Label;
+/// use candid::IDLValue;
+/// use ic_candy::idl::from_idl_value;
+/// use ic_candy::types::PropertyShared;
+/// use ic_candy::value::ToCandyValue;
+///
+/// let record = IDLValue::Record(vec![
+///     IDLField { id: Label::Named("name".to_string()), val: IDLValue::Text("candy".to_string()) },
+///     IDLField { id: Label::Named("tags".to_string()), val: IDLValue::Vec(vec![IDLValue::Nat(1_u8.into())]) },
+/// ]);
+/// assert_eq!(
+///     from_idl_value(record),
+///     Ok(vec![
+///         PropertyShared { name: "name".to_string(), value: "candy".to_candy(), immutable: false },
+///         PropertyShared { name: "tags".to_string(), value: vec![1_u128].to_candy(), immutable: false },
+///     ]
+///     .to_candy())
+/// );
+/// ```
+pub fn from_idl_value(value: IDLValue) -> Result<CandyShared, IdlError> {
+    Ok(match value {
+        IDLValue::Bool(val) => CandyShared::Bool(val),
+        IDLValue::Null | IDLValue::Reserved | IDLValue::None => CandyShared::Option(None),
+        IDLValue::Text(val) => CandyShared::Text(val),
+        IDLValue::Number(val) => from_number(&val)?,
+        IDLValue::Float64(val) => CandyShared::Float(val),
+        IDLValue::Float32(val) => CandyShared::Float(val.into()),
+        IDLValue::Opt(val) => CandyShared::Option(Some(Box::new(from_idl_value(*val)?))),
+        IDLValue::Vec(val) => from_vec(val)?,
+        IDLValue::Record(val) => CandyShared::Class(
+            val.into_iter()
+                .map(|field| {
+                    Ok(PropertyShared {
+                        name: label_name(field.id),
+                        value: from_idl_value(field.val)?,
+                        immutable: false,
+                    })
+                })
+                .collect::<Result<_, IdlError>>()?,
+        ),
+        IDLValue::Variant(VariantValue(field, _)) => CandyShared::Class(vec![
+            PropertyShared {
+                name: VARIANT_TAG.to_string(),
+                value: CandyShared::Text(label_name(field.id)),
+                immutable: true,
+            },
+            PropertyShared {
+                name: VARIANT_VALUE.to_string(),
+                value: from_idl_value(field.val)?,
+                immutable: true,
+            },
+        ]),
+        IDLValue::Blob(val) => CandyShared::Blob(val),
+        IDLValue::Principal(val) => CandyShared::Principal(val),
+        IDLValue::Service(val) => {
+            return Err(IdlError::Unrepresentable(format!("service \"{}\"", val)))
+        }
+        IDLValue::Func(val, method) => {
+            return Err(IdlError::Unrepresentable(format!(
+                "func \"{}\".{}",
+                val, method
+            )))
+        }
+        IDLValue::Int(val) => CandyShared::Int(val),
+        IDLValue::Nat(val) => CandyShared::Nat(val),
+        IDLValue::Nat8(val) => CandyShared::Nat8(val),
+        IDLValue::Nat16(val) => CandyShared::Nat16(val),
+        IDLValue::Nat32(val) => CandyShared::Nat32(val),
+        IDLValue::Nat64(val) => CandyShared::Nat64(val),
+        IDLValue::Int8(val) => CandyShared::Int8(val),
+        IDLValue::Int16(val) => CandyShared::Int16(val),
+        IDLValue::Int32(val) => CandyShared::Int32(val),
+        IDLValue::Int64(val) => CandyShared::Int64(val),
+    })
+}
+
+/// Converts a `CandyShared` into a dynamically typed Candid value, reversing `from_idl_value`:
+///
+/// * `Option(None)` into `null`, which decodes as `null`, `reserved` or an empty `opt` of any type,
+/// * `Blob` into `blob`, `Bytes` into a `vec` of `nat8` values, `Nats`, `Ints` and `Floats` into
+///   `vec nat`, `vec int` and `vec float64`, `Array` and `Set` into `vec`,
+/// * `Class` into a `record`, or into a `variant` if it has exactly the immutable `VARIANT_TAG` and
+///   `VARIANT_VALUE` properties,
+/// * `Map` into `vec record { text; value }` and `ValueMap` into `vec record { key; value }`.
+///
+/// Property names that are numbers become numeric field ids. Fails with `IdlError::DuplicateLabel`
+/// if two properties map to the same field id.
+/// The items of an `Array` must share one Candid type for the result to be encodable.
+///
+/// ```
+/// use candid::IDLValue;
+/// use ic_candy::idl::to_idl_value;
+/// use ic_candy::value::{CandyShared, ToCandyValue};
+///
+/// assert_eq!(
+///     to_idl_value(&CandyShared::Option(Some(Box::new(vec![1_u8, 2].to_candy())))),
+///     Ok(IDLValue::Opt(Box::new(IDLValue::Blob(vec![1, 2]))))
+/// );
+/// ```
+pub fn to_idl_value(value: &CandyShared) -> Result<IDLValue, IdlError> {
+    Ok(match value {
+        CandyShared::Int(val) => IDLValue::Int(val.clone()),
+        CandyShared::Int8(val) => IDLValue::Int8(*val),
+        CandyShared::Int16(val) => IDLValue::Int16(*val),
+        CandyShared::Int32(val) => IDLValue::Int32(*val),
+        CandyShared::Int64(val) => IDLValue::Int64(*val),
+        CandyShared::Ints(val) => IDLValue::Vec(val.iter().cloned().map(IDLValue::Int).collect()),
+        CandyShared::Nat(val) => IDLValue::Nat(val.clone()),
+        CandyShared::Nat8(val) => IDLValue::Nat8(*val),
+        CandyShared::Nat16(val) => IDLValue::Nat16(*val),
+        CandyShared::Nat32(val) => IDLValue::Nat32(*val),
+        CandyShared::Nat64(val) => IDLValue::Nat64(*val),
+        CandyShared::Float(val) => IDLValue::Float64(*val),
+        CandyShared::Text(val) => IDLValue::Text(val.clone()),
+        CandyShared::Bool(val) => IDLValue::Bool(*val),
+        CandyShared::Blob(val) => IDLValue::Blob(val.clone()),
+        CandyShared::Bytes(val) => {
+            IDLValue::Vec(val.iter().map(|val| IDLValue::Nat8(*val)).collect())
+        }
+        CandyShared::Class(val) => match val.as_slice() {
+            [PropertyShared {
+                name: tag,
+                value: CandyShared::Text(label),
+                immutable: true,
+            }, PropertyShared {
+                name: value_name,
+                value,
+                immutable: true,
+            }] if tag == VARIANT_TAG && value_name == VARIANT_VALUE => {
+                IDLValue::Variant(VariantValue(
+                    Box::new(IDLField {
+                        id: to_label(label),
+                        val: to_idl_value(value)?,
+                    }),
+                    0,
+                ))
+            }
+            _ => {
+                let mut fields = val
+                    .iter()
+                    .map(|prop| {
+                        Ok(IDLField {
+                            id: to_label(&prop.name),
+                            val: to_idl_value(&prop.value)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, IdlError>>()?;
+                // Candid records are ordered by field id.
+                fields.sort_by_key(|field| field.id.get_id());
+                if let Some(pair) = fields
+                    .windows(2)
+                    .find(|pair| pair[0].id.get_id() == pair[1].id.get_id())
+                {
+                    return Err(IdlError::DuplicateLabel(label_name(pair[1].id.clone())));
+                }
+                IDLValue::Record(fields)
+            }
+        },
+        CandyShared::Principal(val) => IDLValue::Principal(*val),
+        CandyShared::Option(None) => IDLValue::Null,
+        CandyShared::Option(Some(val)) => IDLValue::Opt(Box::new(to_idl_value(val)?)),
+        CandyShared::Array(val) => {
+            IDLValue::Vec(val.iter().map(to_idl_value).collect::<Result<_, _>>()?)
+        }
+        CandyShared::Nats(val) => IDLValue::Vec(val.iter().cloned().map(IDLValue::Nat).collect()),
+        CandyShared::Floats(val) => {
+            IDLValue::Vec(val.iter().map(|val| IDLValue::Float64(*val)).collect())
+        }
+        CandyShared::Map(val) => IDLValue::Vec(
+            val.iter()
+                .map(|(key, val)| Ok(tuple(IDLValue::Text(key.clone()), to_idl_value(val)?)))
+                .collect::<Result<_, IdlError>>()?,
+        ),
+        CandyShared::ValueMap(val) => IDLValue::Vec(
+            val.iter()
+                .map(|(key, val)| Ok(tuple(to_idl_value(key)?, to_idl_value(val)?)))
+                .collect::<Result<_, IdlError>>()?,
+        ),
+        CandyShared::Set(val) => {
+            IDLValue::Vec(val.iter().map(to_idl_value).collect::<Result<_, _>>()?)
+        }
+    })
+}
+
+fn from_number(number: &str) -> Result<CandyShared, IdlError> {
+    let digits = number.replace('_', "");
+    if let Ok(nat) = digits.parse::<candid::Nat>() {
+        Ok(CandyShared::Nat(nat))
+    } else if let Ok(int) = digits.parse::<candid::Int>() {
+        Ok(CandyShared::Int(int))
+    } else if let Ok(float) = digits.parse::<f64>() {
+        Ok(CandyShared::Float(float))
+    } else {
+        Err(IdlError::InvalidNumber(number.to_string()))
+    }
+}
+
+fn from_vec(items: Vec<IDLValue>) -> Result<CandyShared, IdlError> {
+    if items.is_empty() {
+        return Ok(CandyShared::Array(Vec::new()));
+    }
+    let all = |f: fn(&IDLValue) -> bool| items.iter().all(f);
+    Ok(if all(|item| matches!(item, IDLValue::Nat8(_))) {
+        CandyShared::Bytes(
+            items
+                .into_iter()
+                .filter_map(|item| match item {
+                    IDLValue::Nat8(val) => Some(val),
+                    _ => None,
+                })
+                .collect(),
+        )
+    } else if all(|item| matches!(item, IDLValue::Nat(_))) {
+        CandyShared::Nats(
+            items
+                .into_iter()
+                .filter_map(|item| match item {
+                    IDLValue::Nat(val) => Some(val),
+                    _ => None,
+                })
+                .collect(),
+        )
+    } else if all(|item| matches!(item, IDLValue::Int(_))) {
+        CandyShared::Ints(
+            items
+                .into_iter()
+                .filter_map(|item| match item {
+                    IDLValue::Int(val) => Some(val),
+                    _ => None,
+                })
+                .collect(),
+        )
+    } else if all(|item| matches!(item, IDLValue::Float64(_))) {
+        CandyShared::Floats(
+            items
+                .into_iter()
+                .filter_map(|item| match item {
+                    IDLValue::Float64(val) => Some(val),
+                    _ => None,
+                })
+                .collect(),
+        )
+    } else {
+        CandyShared::Array(
+            items
+                .into_iter()
+                .map(from_idl_value)
+                .collect::<Result<_, _>>()?,
+        )
+    })
+}
+
+fn label_name(label: Label) -> String {
+    match label {
+        Label::Named(name) => name,
+        Label::Id(id) | Label::Unnamed(id) => id.to_string(),
+    }
+}
+
+fn to_label(name: &str) -> Label {
+    match name.parse::<u32>() {
+        Ok(id) => Label::Id(id),
+        Err(_) => Label::Named(name.to_string()),
+    }
+}
+
+fn tuple(first: IDLValue, second: IDLValue) -> IDLValue {
+    IDLValue::Record(vec![
+        IDLField {
+            id: Label::Unnamed(0),
+            val: first,
+        },
+        IDLField {
+            id: Label::Unnamed(1),
+            val: second,
+        },
+    ])
+}
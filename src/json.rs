@@ -0,0 +1,994 @@
+use std::fmt::{self, Display, Write};
+
+use num_bigint::{BigInt, BigUint};
+
+use crate::types::{CandyError, PropertyShared};
+use crate::value::CandyShared;
+
+/// Nesting depth after which parsing is aborted, same as `serde_json`.
+const MAX_DEPTH: usize = 128;
+
+/// Options controlling how `CandyShared::from_json_with` maps JSON onto `CandyShared`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JsonParseOptions {
+    /// Parse arrays of integers into `Nats` or `Ints` and arrays of floats into `Floats` instead of `Array`.
+    /// Arrays mixing integers and floats stay an `Array`.
+    pub typed_arrays: bool,
+    /// Parse non-empty strings made of an even number of hex digits into `Blob`.
+    pub hex_as_blob: bool,
+}
+
+// A parsed JSON document. Numbers keep their literal so that no precision is lost
+// before the target `CandyShared` variant is known.
+enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+pub(crate) fn parse(json: &str, options: &JsonParseOptions) -> Result<CandyShared, CandyError> {
+    parse_document(json).and_then(|value| json_to_candy(value, options))
+}
+
+fn parse_document(json: &str) -> Result<JsonValue, CandyError> {
+    let mut parser = Parser {
+        input: json.as_bytes(),
+        pos: 0,
+    };
+    parser.skip_whitespace();
+    let value = parser.parse_value(0)?;
+    parser.skip_whitespace();
+    if parser.pos != parser.input.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+fn json_to_candy(value: JsonValue, options: &JsonParseOptions) -> Result<CandyShared, CandyError> {
+    Ok(match value {
+        JsonValue::Null => CandyShared::Option(None),
+        JsonValue::Bool(val) => CandyShared::Bool(val),
+        JsonValue::Number(literal) => number_to_candy(&literal)?,
+        JsonValue::String(text) => string_to_candy(text, options),
+        JsonValue::Array(items) => array_to_candy(
+            items
+                .into_iter()
+                .map(|i| json_to_candy(i, options))
+                .collect::<Result<Vec<CandyShared>, CandyError>>()?,
+            options,
+        ),
+        JsonValue::Object(entries) => CandyShared::Class(
+            entries
+                .into_iter()
+                .map(|(name, value)| {
+                    Ok(PropertyShared {
+                        name,
+                        value: json_to_candy(value, options)?,
+                        immutable: false,
+                    })
+                })
+                .collect::<Result<Vec<PropertyShared>, CandyError>>()?,
+        ),
+    })
+}
+
+fn invalid_number(literal: &str) -> CandyError {
+    CandyError::InvalidEncoding(format!("invalid number {}", literal))
+}
+
+fn number_to_candy(literal: &str) -> Result<CandyShared, CandyError> {
+    if literal.contains(['.', 'e', 'E']) {
+        literal
+            .parse::<f64>()
+            .map(CandyShared::Float)
+            .map_err(|_| invalid_number(literal))
+    } else if literal.starts_with('-') {
+        literal
+            .parse::<BigInt>()
+            .map(|val| CandyShared::Int(candid::Int(val)))
+            .map_err(|_| invalid_number(literal))
+    } else {
+        literal
+            .parse::<BigUint>()
+            .map(|val| CandyShared::Nat(candid::Nat(val)))
+            .map_err(|_| invalid_number(literal))
+    }
+}
+
+fn string_to_candy(text: String, options: &JsonParseOptions) -> CandyShared {
+    if options.hex_as_blob && !text.is_empty() {
+        if let Ok(blob) = hex::decode(&text) {
+            return CandyShared::Blob(blob);
+        }
+    }
+    CandyShared::Text(text)
+}
+
+fn array_to_candy(items: Vec<CandyShared>, options: &JsonParseOptions) -> CandyShared {
+    if !options.typed_arrays || items.is_empty() {
+        return CandyShared::Array(items);
+    }
+    let all = |f: fn(&CandyShared) -> bool| items.iter().all(f);
+    if all(|i| matches!(i, CandyShared::Nat(_))) {
+        CandyShared::Nats(
+            items
+                .into_iter()
+                .filter_map(|i| match i {
+                    CandyShared::Nat(val) => Some(val),
+                    _ => None,
+                })
+                .collect(),
+        )
+    } else if all(|i| matches!(i, CandyShared::Nat(_) | CandyShared::Int(_))) {
+        CandyShared::Ints(
+            items
+                .into_iter()
+                .filter_map(|i| match i {
+                    CandyShared::Nat(val) => Some(candid::Int(BigInt::from(val.0))),
+                    CandyShared::Int(val) => Some(val),
+                    _ => None,
+                })
+                .collect(),
+        )
+    } else if all(|i| matches!(i, CandyShared::Float(_))) {
+        // Integers mixed with floats stay an `Array`, converting them could lose precision.
+        CandyShared::Floats(
+            items
+                .into_iter()
+                .filter_map(CandyShared::to_float)
+                .collect(),
+        )
+    } else {
+        CandyShared::Array(items)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> CandyError {
+        CandyError::InvalidEncoding(format!("{} at position {}", msg, self.pos))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), CandyError> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, CandyError> {
+        if self.input[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<JsonValue, CandyError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("recursion limit exceeded"));
+        }
+        match self.peek() {
+            Some(b'{') => self.parse_object(depth),
+            Some(b'[') => self.parse_array(depth),
+            Some(b'"') => self.parse_string().map(JsonValue::String),
+            Some(b't') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some(b'f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some(b'n') => self.parse_literal("null", JsonValue::Null),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<JsonValue, CandyError> {
+        self.expect(b'{')?;
+        let mut entries: Vec<(String, JsonValue)> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let name = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            self.skip_whitespace();
+            let value = self.parse_value(depth + 1)?;
+            entries.push((name, value));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(entries));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<JsonValue, CandyError> {
+        self.expect(b'[')?;
+        let mut items: Vec<JsonValue> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            self.skip_whitespace();
+            items.push(self.parse_value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, CandyError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => self.skip_digits(),
+            _ => return Err(self.error("invalid number")),
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.skip_digits();
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.skip_digits();
+        }
+        // The scanned range only contains ASCII characters.
+        let literal = std::str::from_utf8(&self.input[start..self.pos]).unwrap();
+        Ok(JsonValue::Number(literal.to_string()))
+    }
+
+    fn skip_digits(&mut self) {
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, CandyError> {
+        self.expect(b'"')?;
+        let mut result = String::new();
+        loop {
+            let start = self.pos;
+            while let Some(byte) = self.peek() {
+                if byte == b'"' || byte == b'\\' || byte < 0x20 {
+                    break;
+                }
+                self.pos += 1;
+            }
+            // `input` comes from a `&str` and the scan stops on ASCII bytes only.
+            result.push_str(std::str::from_utf8(&self.input[start..self.pos]).unwrap());
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(result);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    result.push(self.parse_escape()?);
+                }
+                Some(_) => return Err(self.error("control character in string")),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<char, CandyError> {
+        let escaped = self
+            .peek()
+            .ok_or_else(|| self.error("unterminated string"))?;
+        self.pos += 1;
+        Ok(match escaped {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let high = self.parse_hex4()?;
+                let code = if (0xD800..0xDC00).contains(&high) {
+                    self.expect(b'\\')?;
+                    self.expect(b'u')?;
+                    let low = self.parse_hex4()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(self.error("invalid surrogate pair"));
+                    }
+                    0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    high
+                };
+                char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))?
+            }
+            _ => return Err(self.error("invalid escape")),
+        })
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, CandyError> {
+        let digits = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(digits)
+    }
+}
+
+pub(crate) fn to_tagged_json(value: &CandyShared) -> String {
+    let mut out = String::new();
+    write_tagged(value, &mut out);
+    out
+}
+
+pub(crate) fn parse_tagged(json: &str) -> Result<CandyShared, CandyError> {
+    parse_document(json).and_then(tagged_to_candy)
+}
+
+fn write_str(text: &str, out: &mut String) {
+    // Writing into a `String` can not fail.
+    let _ = write_escaped(text, out);
+}
+
+fn write_float(val: f64, out: &mut String) {
+    match val {
+        val if val.is_nan() => out.push_str("\"NaN\""),
+        f64::INFINITY => out.push_str("\"Infinity\""),
+        f64::NEG_INFINITY => out.push_str("\"-Infinity\""),
+        // `Display` for f64 prints the shortest literal that parses back to the same value.
+        val => out.push_str(&val.to_string()),
+    }
+}
+
+fn write_list<T>(items: impl Iterator<Item = T>, out: &mut String, write: impl Fn(T, &mut String)) {
+    out.push('[');
+    for (index, item) in items.enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+        write(item, out);
+    }
+    out.push(']');
+}
+
+fn write_tagged(value: &CandyShared, out: &mut String) {
+    out.push_str("{\"");
+    out.push_str(value.variant_name());
+    out.push_str("\":");
+    match value {
+        CandyShared::Int(val) => write_str(&val.0.to_string(), out),
+        CandyShared::Int8(val) => out.push_str(&val.to_string()),
+        CandyShared::Int16(val) => out.push_str(&val.to_string()),
+        CandyShared::Int32(val) => out.push_str(&val.to_string()),
+        CandyShared::Int64(val) => write_str(&val.to_string(), out),
+        CandyShared::Ints(val) => {
+            write_list(val.iter(), out, |i, out| write_str(&i.0.to_string(), out))
+        }
+        CandyShared::Nat(val) => write_str(&val.0.to_string(), out),
+        CandyShared::Nat8(val) => out.push_str(&val.to_string()),
+        CandyShared::Nat16(val) => out.push_str(&val.to_string()),
+        CandyShared::Nat32(val) => out.push_str(&val.to_string()),
+        CandyShared::Nat64(val) => write_str(&val.to_string(), out),
+        CandyShared::Float(val) => write_float(*val, out),
+        CandyShared::Text(val) => write_str(val, out),
+        CandyShared::Bool(val) => out.push_str(&val.to_string()),
+        CandyShared::Blob(val) | CandyShared::Bytes(val) => write_str(&hex::encode(val), out),
+        CandyShared::Class(val) => write_list(val.iter(), out, |prop, out| {
+            out.push_str("{\"name\":");
+            write_str(&prop.name, out);
+            out.push_str(",\"value\":");
+            write_tagged(&prop.value, out);
+            out.push_str(",\"immutable\":");
+            out.push_str(&prop.immutable.to_string());
+            out.push('}');
+        }),
+        CandyShared::Principal(val) => write_str(&val.to_text(), out),
+        CandyShared::Option(val) => match val {
+            Some(val) => write_tagged(val, out),
+            None => out.push_str("null"),
+        },
+        CandyShared::Array(val) => write_list(val.iter(), out, write_tagged),
+        CandyShared::Nats(val) => {
+            write_list(val.iter(), out, |i, out| write_str(&i.0.to_string(), out))
+        }
+        CandyShared::Floats(val) => write_list(val.iter(), out, |i, out| write_float(*i, out)),
+        CandyShared::Map(val) => {
+            out.push('{');
+            for (index, (key, value)) in val.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_str(key, out);
+                out.push(':');
+                write_tagged(value, out);
+            }
+            out.push('}');
+        }
+        CandyShared::ValueMap(val) => write_list(val.iter(), out, |(key, value), out| {
+            out.push('[');
+            write_tagged(key, out);
+            out.push(',');
+            write_tagged(value, out);
+            out.push(']');
+        }),
+        CandyShared::Set(val) => write_list(val.iter(), out, write_tagged),
+    }
+    out.push('}');
+}
+
+fn invalid_tagged(expected: &str) -> CandyError {
+    CandyError::InvalidEncoding(format!("expected {} in tagged JSON", expected))
+}
+
+fn tagged_string(value: JsonValue, expected: &str) -> Result<String, CandyError> {
+    match value {
+        JsonValue::String(text) => Ok(text),
+        _ => Err(invalid_tagged(expected)),
+    }
+}
+
+fn tagged_number<T: std::str::FromStr>(value: JsonValue, expected: &str) -> Result<T, CandyError> {
+    match value {
+        JsonValue::Number(literal) | JsonValue::String(literal) => {
+            literal.parse::<T>().map_err(|_| invalid_tagged(expected))
+        }
+        _ => Err(invalid_tagged(expected)),
+    }
+}
+
+fn tagged_float(value: JsonValue) -> Result<f64, CandyError> {
+    match value {
+        JsonValue::String(literal) => match literal.as_str() {
+            "NaN" => Ok(f64::NAN),
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            _ => Err(invalid_tagged("a Float")),
+        },
+        value => tagged_number(value, "a Float"),
+    }
+}
+
+fn tagged_array(value: JsonValue, expected: &str) -> Result<Vec<JsonValue>, CandyError> {
+    match value {
+        JsonValue::Array(items) => Ok(items),
+        _ => Err(invalid_tagged(expected)),
+    }
+}
+
+fn tagged_hex(value: JsonValue, expected: &str) -> Result<Vec<u8>, CandyError> {
+    hex::decode(tagged_string(value, expected)?).map_err(|_| invalid_tagged(expected))
+}
+
+fn tagged_property(value: JsonValue) -> Result<PropertyShared, CandyError> {
+    let entries = match value {
+        JsonValue::Object(entries) => entries,
+        _ => return Err(invalid_tagged("a property")),
+    };
+    let (mut name, mut value, mut immutable) = (None, None, None);
+    for (key, entry) in entries {
+        match key.as_str() {
+            "name" => name = Some(tagged_string(entry, "a property name")?),
+            "value" => value = Some(tagged_to_candy(entry)?),
+            "immutable" => match entry {
+                JsonValue::Bool(val) => immutable = Some(val),
+                _ => return Err(invalid_tagged("a boolean immutable flag")),
+            },
+            _ => return Err(invalid_tagged("name, value and immutable fields")),
+        }
+    }
+    match (name, value, immutable) {
+        (Some(name), Some(value), Some(immutable)) => Ok(PropertyShared {
+            name,
+            value,
+            immutable,
+        }),
+        _ => Err(invalid_tagged("name, value and immutable fields")),
+    }
+}
+
+fn tagged_to_candy(value: JsonValue) -> Result<CandyShared, CandyError> {
+    let (variant, payload) = match value {
+        JsonValue::Object(mut entries) if entries.len() == 1 => entries.remove(0),
+        _ => return Err(invalid_tagged("an object with a single variant")),
+    };
+    Ok(match variant.as_str() {
+        "Int" => CandyShared::Int(candid::Int(tagged_number(payload, "an Int")?)),
+        "Int8" => CandyShared::Int8(tagged_number(payload, "an Int8")?),
+        "Int16" => CandyShared::Int16(tagged_number(payload, "an Int16")?),
+        "Int32" => CandyShared::Int32(tagged_number(payload, "an Int32")?),
+        "Int64" => CandyShared::Int64(tagged_number(payload, "an Int64")?),
+        "Ints" => CandyShared::Ints(
+            tagged_array(payload, "Ints")?
+                .into_iter()
+                .map(|i| tagged_number(i, "an Int").map(candid::Int))
+                .collect::<Result<_, _>>()?,
+        ),
+        "Nat" => CandyShared::Nat(candid::Nat(tagged_number(payload, "a Nat")?)),
+        "Nat8" => CandyShared::Nat8(tagged_number(payload, "a Nat8")?),
+        "Nat16" => CandyShared::Nat16(tagged_number(payload, "a Nat16")?),
+        "Nat32" => CandyShared::Nat32(tagged_number(payload, "a Nat32")?),
+        "Nat64" => CandyShared::Nat64(tagged_number(payload, "a Nat64")?),
+        "Float" => CandyShared::Float(tagged_float(payload)?),
+        "Text" => CandyShared::Text(tagged_string(payload, "a Text")?),
+        "Bool" => match payload {
+            JsonValue::Bool(val) => CandyShared::Bool(val),
+            _ => return Err(invalid_tagged("a Bool")),
+        },
+        "Blob" => CandyShared::Blob(tagged_hex(payload, "a hex Blob")?),
+        "Bytes" => CandyShared::Bytes(tagged_hex(payload, "hex Bytes")?),
+        "Class" => CandyShared::Class(
+            tagged_array(payload, "a Class")?
+                .into_iter()
+                .map(tagged_property)
+                .collect::<Result<_, _>>()?,
+        ),
+        "Principal" => CandyShared::Principal(
+            candid::Principal::from_text(tagged_string(payload, "a Principal")?)
+                .map_err(|_| invalid_tagged("a Principal"))?,
+        ),
+        "Option" => CandyShared::Option(match payload {
+            JsonValue::Null => None,
+            payload => Some(Box::new(tagged_to_candy(payload)?)),
+        }),
+        "Array" => CandyShared::Array(
+            tagged_array(payload, "an Array")?
+                .into_iter()
+                .map(tagged_to_candy)
+                .collect::<Result<_, _>>()?,
+        ),
+        "Nats" => CandyShared::Nats(
+            tagged_array(payload, "Nats")?
+                .into_iter()
+                .map(|i| tagged_number(i, "a Nat").map(candid::Nat))
+                .collect::<Result<_, _>>()?,
+        ),
+        "Floats" => CandyShared::Floats(
+            tagged_array(payload, "Floats")?
+                .into_iter()
+                .map(tagged_float)
+                .collect::<Result<_, _>>()?,
+        ),
+        "Map" => match payload {
+            JsonValue::Object(entries) => CandyShared::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, tagged_to_candy(value)?)))
+                    .collect::<Result<_, CandyError>>()?,
+            ),
+            _ => return Err(invalid_tagged("a Map")),
+        },
+        "ValueMap" => CandyShared::ValueMap(
+            tagged_array(payload, "a ValueMap")?
+                .into_iter()
+                .map(|pair| {
+                    let mut pair = tagged_array(pair, "a key/value pair")?;
+                    if pair.len() != 2 {
+                        return Err(invalid_tagged("a key/value pair"));
+                    }
+                    let value = tagged_to_candy(pair.pop().unwrap())?;
+                    let key = tagged_to_candy(pair.pop().unwrap())?;
+                    Ok((key, value))
+                })
+                .collect::<Result<_, CandyError>>()?,
+        ),
+        "Set" => CandyShared::Set(
+            tagged_array(payload, "a Set")?
+                .into_iter()
+                .map(tagged_to_candy)
+                .collect::<Result<_, _>>()?,
+        ),
+        _ => return Err(invalid_tagged("a CandyShared variant")),
+    })
+}
+
+/// Encoding used for `Blob` and `Bytes` values by `CandyShared::to_json_with`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BinaryEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+/// How `CandyShared::to_json_with` writes `NaN` and infinite `Float` values, which JSON can not represent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFiniteFloats {
+    /// Write `null`.
+    #[default]
+    Null,
+    /// Write the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    String,
+}
+
+/// Options for `CandyShared::to_json_with`.
+///
+/// ```
+/// use ic_candy::json::{BinaryEncoding, JsonOptions};
+///
+/// let options = JsonOptions::new()
+///     .indent(2)
+///     .big_numbers_as_strings(1 << 53)
+///     .native_bools(true)
+///     .binary_encoding(BinaryEncoding::Base64);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JsonOptions {
+    indent: Option<usize>,
+    number_string_threshold: Option<u64>,
+    native_bools: bool,
+    binary_encoding: BinaryEncoding,
+    non_finite_floats: NonFiniteFloats,
+}
+
+impl JsonOptions {
+    /// Compact output, numbers and hex blobs as-is, booleans quoted like `to_json` and non-finite floats as `null`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pretty print with the given number of spaces per nesting level.
+    pub fn indent(mut self, spaces: usize) -> Self {
+        self.indent = Some(spaces);
+        self
+    }
+
+    /// Write integers whose absolute value is greater than `threshold` as strings.
+    /// `1 << 53` keeps every number safe for JavaScript clients.
+    pub fn big_numbers_as_strings(mut self, threshold: u64) -> Self {
+        self.number_string_threshold = Some(threshold);
+        self
+    }
+
+    /// Write `Bool` values as JSON `true`/`false` instead of the strings `"true"`/`"false"`.
+    pub fn native_bools(mut self, native: bool) -> Self {
+        self.native_bools = native;
+        self
+    }
+
+    /// Set the encoding of `Blob` and `Bytes` values.
+    pub fn binary_encoding(mut self, encoding: BinaryEncoding) -> Self {
+        self.binary_encoding = encoding;
+        self
+    }
+
+    /// Set how `NaN` and infinite floats are written.
+    pub fn non_finite_floats(mut self, policy: NonFiniteFloats) -> Self {
+        self.non_finite_floats = policy;
+        self
+    }
+}
+
+pub(crate) fn to_json_with(value: &CandyShared, options: &JsonOptions) -> String {
+    let mut out = String::new();
+    JsonWriter { options }
+        .write_value(value, 0, &mut out)
+        .expect("writing JSON into a String");
+    out
+}
+
+pub(crate) fn write_json_with<W: Write>(
+    value: &CandyShared,
+    options: &JsonOptions,
+    out: &mut W,
+) -> fmt::Result {
+    JsonWriter { options }.write_value(value, 0, out)
+}
+
+/// A writer that forwards to `inner` until `limit` bytes have been written and then fails, for bounding
+/// the output of `CandyShared::write_json` and `write_json_with`. The output is truncated at the limit,
+/// on a character boundary, and `limit_reached` tells the limit apart from a failure of `inner`.
+///
+/// ```
+/// use ic_candy::json::LimitedWriter;
+/// use ic_candy::value::ToCandyValue;
+///
+/// let value = vec!["some long text".to_candy()].to_candy();
+/// let mut out = String::new();
+/// let mut limited = LimitedWriter::new(&mut out, 8);
+/// assert!(value.write_json(&mut limited).is_err());
+/// assert!(limited.limit_reached());
+/// assert_eq!(out, r#"["some l"#);
+/// ```
+pub struct LimitedWriter<'a, W: Write> {
+    inner: &'a mut W,
+    remaining: usize,
+    limit_reached: bool,
+}
+
+impl<'a, W: Write> LimitedWriter<'a, W> {
+    pub fn new(inner: &'a mut W, limit: usize) -> Self {
+        Self {
+            inner,
+            remaining: limit,
+            limit_reached: false,
+        }
+    }
+
+    /// Whether a write was cut short by the limit.
+    pub fn limit_reached(&self) -> bool {
+        self.limit_reached
+    }
+}
+
+impl<W: Write> Write for LimitedWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.len() <= self.remaining {
+            self.remaining -= s.len();
+            return self.inner.write_str(s);
+        }
+        let mut end = self.remaining;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.remaining = 0;
+        self.inner.write_str(&s[..end])?;
+        self.limit_reached = true;
+        Err(fmt::Error)
+    }
+}
+
+fn write_escaped<W: Write>(text: &str, out: &mut W) -> fmt::Result {
+    out.write_char('"')?;
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        let escaped = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{8}' => "\\b",
+            '\u{c}' => "\\f",
+            c if c < ' ' => "",
+            _ => continue,
+        };
+        out.write_str(&text[start..index])?;
+        match escaped {
+            "" => write!(out, "\\u{:04x}", c as u32)?,
+            escaped => out.write_str(escaped)?,
+        }
+        start = index + c.len_utf8();
+    }
+    out.write_str(&text[start..])?;
+    out.write_char('"')
+}
+
+struct JsonWriter<'a> {
+    options: &'a JsonOptions,
+}
+
+impl JsonWriter<'_> {
+    fn newline<W: Write>(&self, depth: usize, out: &mut W) -> fmt::Result {
+        if let Some(spaces) = self.options.indent {
+            out.write_char('\n')?;
+            for _ in 0..spaces * depth {
+                out.write_char(' ')?;
+            }
+        }
+        Ok(())
+    }
+
+    // Writes the items surrounded by `open` and `close`, using `write` for each item
+    fn write_items<W: Write, T>(
+        &self,
+        items: impl Iterator<Item = T>,
+        (open, close): (char, char),
+        depth: usize,
+        out: &mut W,
+        mut write: impl FnMut(T, &mut W) -> fmt::Result,
+    ) -> fmt::Result {
+        out.write_char(open)?;
+        let mut empty = true;
+        for item in items {
+            if !empty {
+                out.write_char(',')?;
+            }
+            empty = false;
+            self.newline(depth + 1, out)?;
+            write(item, out)?;
+        }
+        if !empty {
+            self.newline(depth, out)?;
+        }
+        out.write_char(close)
+    }
+
+    fn write_entry<W: Write>(
+        &self,
+        key: &str,
+        value: &CandyShared,
+        depth: usize,
+        out: &mut W,
+    ) -> fmt::Result {
+        write_escaped(key, out)?;
+        out.write_char(':')?;
+        if self.options.indent.is_some() {
+            out.write_char(' ')?;
+        }
+        self.write_value(value, depth, out)
+    }
+
+    fn write_integer<W: Write>(
+        &self,
+        magnitude: &BigUint,
+        literal: impl Display,
+        out: &mut W,
+    ) -> fmt::Result {
+        match self.options.number_string_threshold {
+            Some(threshold) if *magnitude > BigUint::from(threshold) => {
+                write!(out, "\"{}\"", literal)
+            }
+            _ => write!(out, "{}", literal),
+        }
+    }
+
+    fn write_float<W: Write>(&self, val: f64, out: &mut W) -> fmt::Result {
+        match val {
+            val if val.is_finite() => write!(out, "{}", val),
+            _ if self.options.non_finite_floats == NonFiniteFloats::Null => out.write_str("null"),
+            val if val.is_nan() => out.write_str("\"NaN\""),
+            f64::INFINITY => out.write_str("\"Infinity\""),
+            _ => out.write_str("\"-Infinity\""),
+        }
+    }
+
+    fn write_binary<W: Write>(&self, val: &[u8], out: &mut W) -> fmt::Result {
+        out.write_char('"')?;
+        match self.options.binary_encoding {
+            BinaryEncoding::Hex => {
+                for byte in val {
+                    write!(out, "{:02x}", byte)?;
+                }
+            }
+            BinaryEncoding::Base64 => {
+                use base64::Engine;
+                out.write_str(&base64::engine::general_purpose::STANDARD.encode(val))?;
+            }
+        }
+        out.write_char('"')
+    }
+
+    fn write_value<W: Write>(&self, value: &CandyShared, depth: usize, out: &mut W) -> fmt::Result {
+        match value {
+            CandyShared::Int(val) => self.write_integer(val.0.magnitude(), &val.0, out),
+            CandyShared::Nat(val) => self.write_integer(&val.0, &val.0, out),
+            CandyShared::Int64(val) => {
+                self.write_integer(&BigUint::from(val.unsigned_abs()), val, out)
+            }
+            CandyShared::Nat64(val) => self.write_integer(&BigUint::from(*val), val, out),
+            CandyShared::Int8(val) => {
+                self.write_integer(&BigUint::from(val.unsigned_abs()), val, out)
+            }
+            CandyShared::Int16(val) => {
+                self.write_integer(&BigUint::from(val.unsigned_abs()), val, out)
+            }
+            CandyShared::Int32(val) => {
+                self.write_integer(&BigUint::from(val.unsigned_abs()), val, out)
+            }
+            CandyShared::Nat8(val) => self.write_integer(&BigUint::from(*val), val, out),
+            CandyShared::Nat16(val) => self.write_integer(&BigUint::from(*val), val, out),
+            CandyShared::Nat32(val) => self.write_integer(&BigUint::from(*val), val, out),
+            CandyShared::Float(val) => self.write_float(*val, out),
+            CandyShared::Text(val) => write_escaped(val, out),
+            CandyShared::Bool(val) => match self.options.native_bools {
+                true => write!(out, "{}", val),
+                false => write!(out, "\"{}\"", val),
+            },
+            CandyShared::Blob(val) | CandyShared::Bytes(val) => self.write_binary(val, out),
+            CandyShared::Principal(val) => write!(out, "\"{}\"", val),
+            CandyShared::Option(val) => match val {
+                Some(val) => self.write_value(val, depth, out),
+                None => out.write_str("null"),
+            },
+            CandyShared::Class(val) => {
+                self.write_items(val.iter(), ('{', '}'), depth, out, |prop, out| {
+                    self.write_entry(&prop.name, &prop.value, depth + 1, out)
+                })
+            }
+            CandyShared::Array(val) => {
+                self.write_items(val.iter(), ('[', ']'), depth, out, |i, out| {
+                    self.write_value(i, depth + 1, out)
+                })
+            }
+            CandyShared::Nats(val) => {
+                self.write_items(val.iter(), ('[', ']'), depth, out, |i, out| {
+                    self.write_integer(&i.0, &i.0, out)
+                })
+            }
+            CandyShared::Ints(val) => {
+                self.write_items(val.iter(), ('[', ']'), depth, out, |i, out| {
+                    self.write_integer(i.0.magnitude(), &i.0, out)
+                })
+            }
+            CandyShared::Floats(val) => {
+                self.write_items(val.iter(), ('[', ']'), depth, out, |i, out| {
+                    self.write_float(*i, out)
+                })
+            }
+            CandyShared::Map(val) => {
+                self.write_items(val.iter(), ('{', '}'), depth, out, |(key, value), out| {
+                    self.write_entry(key, value, depth + 1, out)
+                })
+            }
+            CandyShared::ValueMap(val) => {
+                let text_keys = val.keys().all(|key| matches!(key, CandyShared::Text(_)));
+                match text_keys {
+                    true => {
+                        self.write_items(val.iter(), ('{', '}'), depth, out, |(key, value), out| {
+                            self.write_value(key, depth + 1, out)?;
+                            out.write_char(':')?;
+                            if self.options.indent.is_some() {
+                                out.write_char(' ')?;
+                            }
+                            self.write_value(value, depth + 1, out)
+                        })
+                    }
+                    false => {
+                        self.write_items(val.iter(), ('[', ']'), depth, out, |(key, value), out| {
+                            out.write_char('[')?;
+                            self.newline(depth + 2, out)?;
+                            self.write_value(key, depth + 2, out)?;
+                            out.write_char(',')?;
+                            self.newline(depth + 2, out)?;
+                            self.write_value(value, depth + 2, out)?;
+                            self.newline(depth + 1, out)?;
+                            out.write_char(']')
+                        })
+                    }
+                }
+            }
+            CandyShared::Set(val) => {
+                self.write_items(val.iter(), ('[', ']'), depth, out, |item, out| {
+                    self.write_value(item, depth + 1, out)
+                })
+            }
+        }
+    }
+}
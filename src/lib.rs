@@ -1,5 +1,27 @@
 extern crate core;
 
+pub mod conversion;
+pub mod de;
+#[doc(hidden)]
+pub mod derive;
+pub mod diff;
+pub mod hashing;
+pub mod icrc;
+#[cfg(feature = "icrc16")]
+pub mod icrc16;
+pub mod idl;
+pub mod json;
+pub mod merge;
+pub mod patch;
+pub mod path;
+pub mod properties;
+pub mod ser;
 pub mod types;
 pub mod value;
 pub mod workspace;
+
+pub use de::from_candy_value;
+pub use ser::to_candy_value;
+
+#[cfg(feature = "derive")]
+pub use ic_candy_derive::{FromCandy, ToCandy};
@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::path::{CandyPath, PathSegment};
+use crate::types::PropertyShared;
+use crate::value::CandyShared;
+
+/// Which side is kept when `merge` finds two different values at the same path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    #[default]
+    OverlayWins,
+    BaseWins,
+    /// Fail with `MergeError::Conflict`.
+    Error,
+}
+
+/// How `merge` combines two `Array` values at the same path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayPolicy {
+    /// Treat the arrays like any other value, i.e. resolve them with the `ConflictPolicy`.
+    #[default]
+    Replace,
+    /// Append the overlay items to the base items.
+    Concat,
+}
+
+/// Options for `merge`, by default the overlay wins and arrays are replaced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MergeStrategy {
+    pub conflicts: ConflictPolicy,
+    pub arrays: ArrayPolicy,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum MergeError {
+    /// The values at the given path differ and the strategy uses `ConflictPolicy::Error`.
+    Conflict(CandyPath),
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeError::Conflict(path) => write!(f, "merge conflict at {}", path),
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// Deep merges `overlay` into `base`.
+///
+/// `Class` properties, `Map` and `ValueMap` entries and `Option(Some(..))` contents present on both sides
+/// are merged recursively, entries only present on one side are kept, new `Class` properties are appended
+/// after the base ones. Other differing values are resolved with the `ConflictPolicy`, arrays may be
+/// concatenated instead.
+///
+/// A property marked `immutable` in `base` is never overridden: its base value is kept, or reported as a
+/// conflict with `ConflictPolicy::Error` if the overlay value differs. The same holds for a base value
+/// holding immutable properties that the overlay would replace rather than merge into, e.g. with a
+/// different variant. A property is immutable in the result if it is immutable on either side.
+///
+/// ```
+/// use ic_candy::merge::{merge, MergeStrategy};
+/// use ic_candy::types::PropertyShared;
+/// use ic_candy::value::ToCandyValue;
+///
+/// let prop = |name: &str, value: &str, immutable| PropertyShared {
+///     name: name.to_string(),
+///     value: value.to_candy(),
+///     immutable,
+/// };
+/// let defaults = vec![prop("creator", "alice", true), prop("name", "untitled", false)].to_candy();
+/// let token = vec![prop("creator", "bob", false), prop("name", "candy", false)].to_candy();
+///
+/// assert_eq!(
+///     merge(&defaults, &token, MergeStrategy::default()),
+///     Ok(vec![prop("creator", "alice", true), prop("name", "candy", false)].to_candy())
+/// );
+/// ```
+pub fn merge(
+    base: &CandyShared,
+    overlay: &CandyShared,
+    strategy: MergeStrategy,
+) -> Result<CandyShared, MergeError> {
+    merge_at(&CandyPath::root(), base, overlay, strategy)
+}
+
+fn resolve(
+    path: &CandyPath,
+    base: &CandyShared,
+    overlay: &CandyShared,
+    conflicts: ConflictPolicy,
+) -> Result<CandyShared, MergeError> {
+    match conflicts {
+        _ if base == overlay => Ok(base.clone()),
+        ConflictPolicy::OverlayWins => Ok(overlay.clone()),
+        ConflictPolicy::BaseWins => Ok(base.clone()),
+        ConflictPolicy::Error => Err(MergeError::Conflict(path.clone())),
+    }
+}
+
+fn merge_at(
+    path: &CandyPath,
+    base: &CandyShared,
+    overlay: &CandyShared,
+    strategy: MergeStrategy,
+) -> Result<CandyShared, MergeError> {
+    match (base, overlay) {
+        (CandyShared::Option(Some(base)), CandyShared::Option(Some(overlay))) => Ok(
+            CandyShared::Option(Some(Box::new(merge_at(path, base, overlay, strategy)?))),
+        ),
+        (CandyShared::Class(base), CandyShared::Class(overlay)) => {
+            let mut merged = Vec::with_capacity(base.len());
+            for base_prop in base {
+                let prop_path = path.join(PathSegment::Key(base_prop.name.clone()));
+                let prop = match overlay.iter().find(|p| p.name == base_prop.name) {
+                    Some(overlay_prop) if base_prop.immutable => PropertyShared {
+                        value: resolve(
+                            &prop_path,
+                            &base_prop.value,
+                            &overlay_prop.value,
+                            protected(strategy.conflicts),
+                        )?,
+                        ..base_prop.clone()
+                    },
+                    Some(overlay_prop) => PropertyShared {
+                        name: base_prop.name.clone(),
+                        value: merge_at(
+                            &prop_path,
+                            &base_prop.value,
+                            &overlay_prop.value,
+                            strategy,
+                        )?,
+                        immutable: overlay_prop.immutable,
+                    },
+                    None => base_prop.clone(),
+                };
+                merged.push(prop);
+            }
+            merged.extend(
+                overlay
+                    .iter()
+                    .filter(|p| !base.iter().any(|b| b.name == p.name))
+                    .cloned(),
+            );
+            Ok(CandyShared::Class(merged))
+        }
+        (CandyShared::Map(base), CandyShared::Map(overlay)) => Ok(CandyShared::Map(merge_maps(
+            path,
+            base,
+            overlay,
+            |key| PathSegment::Key(key.clone()),
+            strategy,
+        )?)),
+        (CandyShared::ValueMap(base), CandyShared::ValueMap(overlay)) => {
+            Ok(CandyShared::ValueMap(merge_maps(
+                path,
+                base,
+                overlay,
+                |key| match key {
+                    CandyShared::Text(key) => PathSegment::Key(key.clone()),
+                    key => PathSegment::Value(key.clone()),
+                },
+                strategy,
+            )?))
+        }
+        (CandyShared::Array(base), CandyShared::Array(overlay))
+            if strategy.arrays == ArrayPolicy::Concat =>
+        {
+            Ok(CandyShared::Array(
+                base.iter().chain(overlay.iter()).cloned().collect(),
+            ))
+        }
+        // Replacing the base value would drop the immutable properties inside it.
+        (base, overlay) if base.holds_immutable() => {
+            resolve(path, base, overlay, protected(strategy.conflicts))
+        }
+        (base, overlay) => resolve(path, base, overlay, strategy.conflicts),
+    }
+}
+
+// The policy for values that must not be overridden: the base value is kept unless conflicts are errors.
+fn protected(conflicts: ConflictPolicy) -> ConflictPolicy {
+    match conflicts {
+        ConflictPolicy::Error => ConflictPolicy::Error,
+        _ => ConflictPolicy::BaseWins,
+    }
+}
+
+fn merge_maps<K: Clone + Ord>(
+    path: &CandyPath,
+    base: &BTreeMap<K, CandyShared>,
+    overlay: &BTreeMap<K, CandyShared>,
+    segment: impl Fn(&K) -> PathSegment,
+    strategy: MergeStrategy,
+) -> Result<BTreeMap<K, CandyShared>, MergeError> {
+    let mut merged = base.clone();
+    for (key, overlay_value) in overlay {
+        let value = match base.get(key) {
+            Some(base_value) => merge_at(
+                &path.join(segment(key)),
+                base_value,
+                overlay_value,
+                strategy,
+            )?,
+            None => overlay_value.clone(),
+        };
+        merged.insert(key.clone(), value);
+    }
+    Ok(merged)
+}
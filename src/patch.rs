@@ -0,0 +1,239 @@
+use std::fmt::Display;
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::path::{self, CandyPath, PathSegment};
+use crate::value::CandyShared;
+
+/// A single JSON-Patch-style (RFC 6902) operation on a `CandyShared` tree.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum CandyPatch {
+    /// Inserts `value` into an `Array` (an index equal to the length appends) or sets a
+    /// `Class` property, `Map`/`ValueMap` entry or `Set` member, replacing an existing one.
+    Add { path: CandyPath, value: CandyShared },
+    /// Removes the existing value at `path`.
+    Remove { path: CandyPath },
+    /// Replaces the existing value at `path`.
+    Replace { path: CandyPath, value: CandyShared },
+    /// Removes the value at `from` and adds it at `path`, which refers to the tree after the removal.
+    Move { from: CandyPath, path: CandyPath },
+    /// Adds a copy of the value at `from` at `path`.
+    Copy { from: CandyPath, path: CandyPath },
+    /// Checks that the value at `path` equals `value`.
+    Test { path: CandyPath, value: CandyShared },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum PatchError {
+    /// There is no value at the given path.
+    NotFound(CandyPath),
+    /// The given path is, or lies below, an immutable `Class` property, or the value at the given path
+    /// holds one and would be removed or replaced.
+    Immutable(CandyPath),
+    /// The value at the given path can not be changed by the operation, e.g. the root value can not be
+    /// removed, a value can not be added below a `Text` and a value can not be moved into itself.
+    InvalidTarget(CandyPath),
+    /// A `Test` operation did not match.
+    TestFailed(CandyPath),
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchError::NotFound(path) => write!(f, "path not found: {}", path),
+            PatchError::Immutable(path) => write!(f, "immutable property: {}", path),
+            PatchError::InvalidTarget(path) => write!(f, "invalid patch target: {}", path),
+            PatchError::TestFailed(path) => write!(f, "test failed: {}", path),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Applies `patches` in order to `value`.
+///
+/// The patches are applied to a copy of `value` which only replaces it if all of them succeed.
+/// Operations changing an immutable `Class` property or anything below it, or removing or replacing a
+/// value that holds one, are refused with `PatchError::Immutable`.
+///
+/// ```
+/// use ic_candy::patch::{apply_patch, CandyPatch, PatchError};
+/// use ic_candy::types::PropertyShared;
+/// use ic_candy::value::ToCandyValue;
+///
+/// let mut value = vec![
+///     PropertyShared { name: "id".to_string(), value: 1_u8.to_candy(), immutable: true },
+///     PropertyShared { name: "name".to_string(), value: "candy".to_candy(), immutable: false },
+/// ]
+/// .to_candy();
+/// let rename = CandyPatch::Replace { path: "name".parse().unwrap(), value: "sugar".to_candy() };
+/// let renumber = CandyPatch::Replace { path: "id".parse().unwrap(), value: 2_u8.to_candy() };
+///
+/// let before = value.clone();
+/// assert_eq!(
+///     apply_patch(&mut value, &[rename.clone(), renumber]),
+///     Err(PatchError::Immutable("id".parse().unwrap()))
+/// );
+/// assert_eq!(value, before);
+///
+/// apply_patch(&mut value, &[rename]).unwrap();
+/// assert_eq!(value.get(&"name".parse().unwrap()), Some(&"sugar".to_candy()));
+/// ```
+pub fn apply_patch(value: &mut CandyShared, patches: &[CandyPatch]) -> Result<(), PatchError> {
+    let mut patched = value.clone();
+    for patch in patches {
+        apply_one(&mut patched, patch)?;
+    }
+    *value = patched;
+    Ok(())
+}
+
+fn apply_one(value: &mut CandyShared, patch: &CandyPatch) -> Result<(), PatchError> {
+    match patch {
+        CandyPatch::Add { path, value: new } => {
+            check_mutable(value, path)?;
+            add(value, path, new.clone())
+        }
+        CandyPatch::Remove { path } => {
+            check_mutable(value, path)?;
+            remove(value, path).map(|_| ())
+        }
+        CandyPatch::Replace { path, value: new } => {
+            check_mutable(value, path)?;
+            match path::get(value, path) {
+                None => return Err(not_found(value, path)),
+                Some(old) => check_replaceable(old, path)?,
+            }
+            path::set(value, path, new.clone())
+                .map(|_| ())
+                .map_err(|_| PatchError::InvalidTarget(path.clone()))
+        }
+        CandyPatch::Move { from, path } => {
+            if from == path {
+                return path::get(value, from)
+                    .map(|_| ())
+                    .ok_or_else(|| not_found(value, from));
+            }
+            if path.segments().starts_with(from.segments()) {
+                return Err(PatchError::InvalidTarget(path.clone()));
+            }
+            check_mutable(value, from)?;
+            // `path` is resolved after the removal, which shifts the `Array` items behind `from`,
+            // so it is checked against the tree without the moved value.
+            let before = value.clone();
+            let moved = remove(value, from)?;
+            let result = check_mutable(value, path).and_then(|_| add(value, path, moved));
+            if result.is_err() {
+                *value = before;
+            }
+            result
+        }
+        CandyPatch::Copy { from, path } => {
+            check_mutable(value, path)?;
+            let copied = path::get(value, from)
+                .cloned()
+                .ok_or_else(|| not_found(value, from))?;
+            add(value, path, copied)
+        }
+        CandyPatch::Test {
+            path,
+            value: expected,
+        } => match path::get(value, path) {
+            Some(actual) if actual == expected => Ok(()),
+            Some(_) => Err(PatchError::TestFailed(path.clone())),
+            None => Err(not_found(value, path)),
+        },
+    }
+}
+
+// Reports the longest existing prefix of `path` plus one segment, i.e. the first missing step.
+fn not_found(value: &CandyShared, path: &CandyPath) -> PatchError {
+    let mut missing = CandyPath::root();
+    let mut current = value;
+    for segment in path.segments() {
+        missing.push(segment.clone());
+        match path::child(current, segment) {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    PatchError::NotFound(missing)
+}
+
+// The immutable properties inside a value are dropped along with it.
+fn check_replaceable(old: &CandyShared, path: &CandyPath) -> Result<(), PatchError> {
+    if old.holds_immutable() {
+        return Err(PatchError::Immutable(path.clone()));
+    }
+    Ok(())
+}
+
+fn check_mutable(value: &CandyShared, path: &CandyPath) -> Result<(), PatchError> {
+    let mut prefix = CandyPath::root();
+    let mut current = value;
+    for segment in path.segments() {
+        prefix.push(segment.clone());
+        if let (CandyShared::Class(props), PathSegment::Key(key)) =
+            (path::unwrap_option(current), segment)
+        {
+            if props.iter().any(|p| &p.name == key && p.immutable) {
+                return Err(PatchError::Immutable(prefix));
+            }
+        }
+        match path::child(current, segment) {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+fn add(value: &mut CandyShared, path: &CandyPath, new: CandyShared) -> Result<(), PatchError> {
+    let (parent_path, last) = match path.split_last() {
+        Some(split) => split,
+        None => {
+            check_replaceable(value, path)?;
+            *value = new;
+            return Ok(());
+        }
+    };
+    if path::get(value, &parent_path).is_none() {
+        return Err(not_found(value, &parent_path));
+    }
+    // `Set` members are reachable but can not be changed in place.
+    let parent = match path::get_mut(value, &parent_path) {
+        Some(parent) => parent,
+        None => return Err(PatchError::InvalidTarget(path.clone())),
+    };
+    match (path::unwrap_option_mut(parent), last) {
+        (CandyShared::Array(items), PathSegment::Index(i)) => {
+            match usize::try_from(*i).ok().filter(|i| *i <= items.len()) {
+                Some(i) => {
+                    items.insert(i, new);
+                    Ok(())
+                }
+                None => Err(PatchError::NotFound(path.clone())),
+            }
+        }
+        _ => {
+            if let Some(old) = path::get(value, path) {
+                check_replaceable(old, path)?;
+            }
+            path::set(value, path, new)
+                .map(|_| ())
+                .map_err(|_| PatchError::InvalidTarget(path.clone()))
+        }
+    }
+}
+
+fn remove(value: &mut CandyShared, path: &CandyPath) -> Result<CandyShared, PatchError> {
+    if path.segments().is_empty() {
+        return Err(PatchError::InvalidTarget(path.clone()));
+    }
+    match path::get(value, path) {
+        None => return Err(not_found(value, path)),
+        Some(old) => check_replaceable(old, path)?,
+    }
+    path::remove(value, path).ok_or_else(|| PatchError::InvalidTarget(path.clone()))
+}
@@ -0,0 +1,335 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{CandyError, PropertyShared};
+use crate::value::CandyShared;
+
+/// A single step of a `CandyPath`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, CandidType, Serialize, Deserialize)]
+pub enum PathSegment {
+    /// A `Class` property name, a `Map` key or a `Text` key of a `ValueMap`.
+    Key(String),
+    /// An `Array` index.
+    Index(u64),
+    /// A `ValueMap` key or a `Set` member, written as its tagged JSON, e.g. `[{"Nat8":3}]`.
+    Value(CandyShared),
+}
+
+/// A path into nested `CandyShared` values, e.g. `metadata.attributes[3].value`.
+///
+/// Keys are separated by `.`, array indices are written as `[n]` and keys containing `.`, `[`, `]` or `"`
+/// can be quoted as `["some.key"]`. Arbitrary `ValueMap` keys and `Set` members are written as their
+/// tagged JSON in brackets, e.g. `[{"Nat8":3}]`. The empty path points to the value itself.
+/// `Option(Some(..))` values are unwrapped transparently while walking the path.
+///
+/// ```
+/// use ic_candy::path::{CandyPath, PathSegment};
+///
+/// let path: CandyPath = "metadata.attributes[3][\"a.b\"]".parse().unwrap();
+/// assert_eq!(
+///     path.segments(),
+///     &[
+///         PathSegment::Key("metadata".to_string()),
+///         PathSegment::Key("attributes".to_string()),
+///         PathSegment::Index(3),
+///         PathSegment::Key("a.b".to_string()),
+///     ]
+/// );
+/// assert_eq!(path.to_string(), "metadata.attributes[3][\"a.b\"]");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, CandidType, Serialize, Deserialize)]
+pub struct CandyPath {
+    segments: Vec<PathSegment>,
+}
+
+impl CandyPath {
+    /// The empty path, pointing to the value itself.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    pub fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+
+    /// Returns a new path with the given segment appended.
+    pub fn join(&self, segment: PathSegment) -> Self {
+        let mut path = self.clone();
+        path.push(segment);
+        path
+    }
+
+    /// Splits the path into its parent path and its last segment, `None` for the root path.
+    pub fn split_last(&self) -> Option<(CandyPath, &PathSegment)> {
+        self.segments.split_last().map(|(last, parent)| {
+            (
+                CandyPath {
+                    segments: parent.to_vec(),
+                },
+                last,
+            )
+        })
+    }
+}
+
+impl From<Vec<PathSegment>> for CandyPath {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self { segments }
+    }
+}
+
+fn is_plain_key(key: &str) -> bool {
+    !key.is_empty() && !key.contains(['.', '[', ']', '"', '\\'])
+}
+
+impl Display for CandyPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if is_plain_key(key) => {
+                    if index > 0 {
+                        write!(f, ".")?;
+                    }
+                    write!(f, "{}", key)?
+                }
+                PathSegment::Key(key) => write!(
+                    f,
+                    "[\"{}\"]",
+                    key.replace('\\', "\\\\").replace('"', "\\\"")
+                )?,
+                PathSegment::Index(i) => write!(f, "[{}]", i)?,
+                PathSegment::Value(value) => write!(f, "[{}]", value.to_tagged_json())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CandyPath {
+    type Err = CandyError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let invalid = || CandyError::InvalidPath(path.to_string());
+        let mut segments = Vec::new();
+        let mut chars = path.chars().peekable();
+        let mut expect_key = true;
+        while let Some(c) = chars.peek().copied() {
+            match c {
+                '[' => {
+                    chars.next();
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        let mut key = String::new();
+                        loop {
+                            match chars.next().ok_or_else(invalid)? {
+                                '"' => break,
+                                '\\' => key.push(chars.next().ok_or_else(invalid)?),
+                                c => key.push(c),
+                            }
+                        }
+                        segments.push(PathSegment::Key(key));
+                    } else if chars.peek() == Some(&'{') {
+                        let json = take_json_object(&mut chars).ok_or_else(invalid)?;
+                        let value = CandyShared::from_tagged_json(&json).map_err(|_| invalid())?;
+                        segments.push(PathSegment::Value(value));
+                    } else {
+                        let mut digits = String::new();
+                        while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                            digits.push(c);
+                        }
+                        segments.push(PathSegment::Index(digits.parse().map_err(|_| invalid())?));
+                    }
+                    if chars.next() != Some(']') {
+                        return Err(invalid());
+                    }
+                    expect_key = false;
+                }
+                '.' if !expect_key => {
+                    chars.next();
+                    expect_key = true;
+                    if chars.peek().is_none() {
+                        return Err(invalid());
+                    }
+                }
+                _ if expect_key => {
+                    let mut key = String::new();
+                    while let Some(c) = chars.next_if(|c| !matches!(c, '.' | '[' | ']' | '"')) {
+                        key.push(c);
+                    }
+                    if key.is_empty() {
+                        return Err(invalid());
+                    }
+                    segments.push(PathSegment::Key(key));
+                    expect_key = false;
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(Self { segments })
+    }
+}
+
+// Consumes a JSON object with balanced braces, skipping braces inside strings.
+fn take_json_object(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    let mut json = String::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    loop {
+        let c = chars.next()?;
+        json.push(c);
+        match c {
+            '\\' if in_string => json.push(chars.next()?),
+            '"' => in_string = !in_string,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(json);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn unwrap_option(value: &CandyShared) -> &CandyShared {
+    match value {
+        CandyShared::Option(Some(inner)) => unwrap_option(inner),
+        value => value,
+    }
+}
+
+pub(crate) fn unwrap_option_mut(value: &mut CandyShared) -> &mut CandyShared {
+    match value {
+        CandyShared::Option(Some(inner)) => unwrap_option_mut(inner),
+        value => value,
+    }
+}
+
+pub(crate) fn child<'a>(value: &'a CandyShared, segment: &PathSegment) -> Option<&'a CandyShared> {
+    match (unwrap_option(value), segment) {
+        (CandyShared::Class(props), PathSegment::Key(key)) => {
+            props.iter().find(|p| &p.name == key).map(|p| &p.value)
+        }
+        (CandyShared::Map(map), PathSegment::Key(key)) => map.get(key),
+        (CandyShared::ValueMap(map), PathSegment::Key(key)) => {
+            map.get(&CandyShared::Text(key.clone()))
+        }
+        (CandyShared::ValueMap(map), PathSegment::Value(key)) => map.get(key),
+        (CandyShared::Set(set), PathSegment::Value(member)) => set.get(member),
+        (CandyShared::Array(items), PathSegment::Index(i)) => items.get(usize::try_from(*i).ok()?),
+        _ => None,
+    }
+}
+
+fn child_mut<'a>(value: &'a mut CandyShared, segment: &PathSegment) -> Option<&'a mut CandyShared> {
+    match (unwrap_option_mut(value), segment) {
+        (CandyShared::Class(props), PathSegment::Key(key)) => props
+            .iter_mut()
+            .find(|p| &p.name == key)
+            .map(|p| &mut p.value),
+        (CandyShared::Map(map), PathSegment::Key(key)) => map.get_mut(key),
+        (CandyShared::ValueMap(map), PathSegment::Key(key)) => {
+            map.get_mut(&CandyShared::Text(key.clone()))
+        }
+        (CandyShared::ValueMap(map), PathSegment::Value(key)) => map.get_mut(key),
+        (CandyShared::Array(items), PathSegment::Index(i)) => {
+            items.get_mut(usize::try_from(*i).ok()?)
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn get<'a>(value: &'a CandyShared, path: &CandyPath) -> Option<&'a CandyShared> {
+    path.segments
+        .iter()
+        .try_fold(value, |value, segment| child(value, segment))
+}
+
+pub(crate) fn get_mut<'a>(
+    value: &'a mut CandyShared,
+    path: &CandyPath,
+) -> Option<&'a mut CandyShared> {
+    path.segments
+        .iter()
+        .try_fold(value, |value, segment| child_mut(value, segment))
+}
+
+pub(crate) fn set(
+    value: &mut CandyShared,
+    path: &CandyPath,
+    new_value: CandyShared,
+) -> Result<Option<CandyShared>, CandyError> {
+    let not_found = || CandyError::PathNotFound(path.to_string());
+    let (parent_path, last) = match path.split_last() {
+        Some(split) => split,
+        None => return Ok(Some(std::mem::replace(value, new_value))),
+    };
+    let parent = get_mut(value, &parent_path).ok_or_else(not_found)?;
+    match (unwrap_option_mut(parent), last) {
+        (CandyShared::Class(props), PathSegment::Key(key)) => {
+            match props.iter_mut().find(|p| &p.name == key) {
+                Some(prop) => Ok(Some(std::mem::replace(&mut prop.value, new_value))),
+                None => {
+                    props.push(PropertyShared {
+                        name: key.clone(),
+                        value: new_value,
+                        immutable: false,
+                    });
+                    Ok(None)
+                }
+            }
+        }
+        (CandyShared::Map(map), PathSegment::Key(key)) => Ok(map.insert(key.clone(), new_value)),
+        (CandyShared::ValueMap(map), PathSegment::Key(key)) => {
+            Ok(map.insert(CandyShared::Text(key.clone()), new_value))
+        }
+        (CandyShared::ValueMap(map), PathSegment::Value(key)) => {
+            Ok(map.insert(key.clone(), new_value))
+        }
+        (CandyShared::Set(set), PathSegment::Value(member)) => {
+            let previous = set.take(member);
+            set.insert(new_value);
+            Ok(previous)
+        }
+        (CandyShared::Array(items), PathSegment::Index(i)) => {
+            match usize::try_from(*i).map_err(|_| not_found())? {
+                i if i < items.len() => Ok(Some(std::mem::replace(&mut items[i], new_value))),
+                i if i == items.len() => {
+                    items.push(new_value);
+                    Ok(None)
+                }
+                _ => Err(not_found()),
+            }
+        }
+        _ => Err(not_found()),
+    }
+}
+
+pub(crate) fn remove(value: &mut CandyShared, path: &CandyPath) -> Option<CandyShared> {
+    let (parent_path, last) = path.split_last()?;
+    match (unwrap_option_mut(get_mut(value, &parent_path)?), last) {
+        (CandyShared::Class(props), PathSegment::Key(key)) => {
+            let index = props.iter().position(|p| &p.name == key)?;
+            Some(props.remove(index).value)
+        }
+        (CandyShared::Map(map), PathSegment::Key(key)) => map.remove(key),
+        (CandyShared::ValueMap(map), PathSegment::Key(key)) => {
+            map.remove(&CandyShared::Text(key.clone()))
+        }
+        (CandyShared::ValueMap(map), PathSegment::Value(key)) => map.remove(key),
+        (CandyShared::Set(set), PathSegment::Value(member)) => set.take(member),
+        (CandyShared::Array(items), PathSegment::Index(i)) => {
+            let i = usize::try_from(*i).ok()?;
+            (i < items.len()).then(|| items.remove(i))
+        }
+        _ => None,
+    }
+}
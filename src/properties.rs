@@ -0,0 +1,124 @@
+use crate::types::{Properties, PropertyError, PropertyShared, Query, Update, UpdateMode};
+use crate::value::CandyShared;
+
+/// Returns the property with the given name, if present.
+pub fn get_class_property(props: &[PropertyShared], name: &str) -> Option<PropertyShared> {
+    props.iter().find(|p| p.name == name).cloned()
+}
+
+/// Retrieves the properties matching the given queries.
+///
+/// Queries with a non-empty `next` descend into `Class` values and only keep the requested
+/// sub-properties. Mirrors `getPropertiesShared` from the Motoko `candy/properties` module.
+///
+/// # Errors
+///
+/// * `PropertyError::NotFound` if a queried property does not exist or a nested query
+///   targets a property that is not a `Class`.
+///
+/// ```
+/// use ic_candy::properties::get_properties;
+/// use ic_candy::types::{PropertyShared, Query};
+/// use ic_candy::value::ToCandyValue;
+///
+/// let props = vec![PropertyShared {
+///     name: "name".to_string(),
+///     value: "candy".to_candy(),
+///     immutable: false,
+/// }];
+/// let query = Query { name: "name".to_string(), next: vec![] };
+/// assert_eq!(get_properties(&props, &[query]), Ok(props.clone()));
+/// ```
+pub fn get_properties(
+    props: &[PropertyShared],
+    queries: &[Query],
+) -> Result<Properties, PropertyError> {
+    let mut result: Properties = Vec::with_capacity(queries.len());
+    for query in queries {
+        let prop = props
+            .iter()
+            .find(|p| p.name == query.name)
+            .ok_or(PropertyError::NotFound)?;
+        if query.next.is_empty() {
+            result.push(prop.clone());
+            continue;
+        }
+        match &prop.value {
+            CandyShared::Class(class) => result.push(PropertyShared {
+                name: prop.name.clone(),
+                value: CandyShared::Class(get_properties(class, &query.next)?),
+                immutable: prop.immutable,
+            }),
+            _ => return Err(PropertyError::NotFound),
+        }
+    }
+    Ok(result)
+}
+
+/// Applies the given updates to the properties and returns the updated properties.
+///
+/// Unknown properties are appended when set, existing ones are replaced in place.
+/// Mirrors `updatePropertiesShared` from the Motoko `candy/properties` module.
+///
+/// # Errors
+///
+/// * `PropertyError::Immutable` if an update targets an immutable property.
+/// * `PropertyError::NotFound` if a nested update targets a missing property or a property
+///   that is not a `Class`.
+///
+/// ```
+/// use ic_candy::properties::update_properties;
+/// use ic_candy::types::{PropertyError, PropertyShared, Update, UpdateMode};
+/// use ic_candy::value::ToCandyValue;
+///
+/// let props = vec![PropertyShared {
+///     name: "name".to_string(),
+///     value: "candy".to_candy(),
+///     immutable: true,
+/// }];
+/// let update = Update {
+///     name: "name".to_string(),
+///     mode: UpdateMode::Set("sugar".to_candy()),
+/// };
+/// assert_eq!(update_properties(&props, &[update]), Err(PropertyError::Immutable));
+/// ```
+pub fn update_properties(
+    props: &[PropertyShared],
+    updates: &[Update],
+) -> Result<Properties, PropertyError> {
+    let mut result: Properties = props.to_vec();
+    for update in updates {
+        match result.iter().position(|p| p.name == update.name) {
+            None => match &update.mode {
+                UpdateMode::Set(value) => result.push(PropertyShared {
+                    name: update.name.clone(),
+                    value: value.clone(),
+                    immutable: false,
+                }),
+                // Nested properties can not be set on a non-existing property.
+                UpdateMode::Next(_) => return Err(PropertyError::NotFound),
+            },
+            Some(index) => {
+                let prop = &result[index];
+                if prop.immutable {
+                    return Err(PropertyError::Immutable);
+                }
+                let value = match &update.mode {
+                    UpdateMode::Set(value) => value.clone(),
+                    UpdateMode::Next(next) => match &prop.value {
+                        CandyShared::Class(class) => {
+                            CandyShared::Class(update_properties(class, next)?)
+                        }
+                        _ => return Err(PropertyError::NotFound),
+                    },
+                };
+                result[index] = PropertyShared {
+                    name: update.name.clone(),
+                    value,
+                    immutable: false,
+                };
+            }
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,438 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use serde::ser::{self, Serialize};
+
+use crate::derive::variant;
+use crate::types::{CandyError, PropertyShared};
+use crate::value::CandyShared;
+
+impl ser::Error for CandyError {
+    fn custom<T: Display>(msg: T) -> Self {
+        CandyError::Custom(msg.to_string())
+    }
+}
+
+/// Converts any `Serialize` type into a `CandyShared`, the reverse of `from_candy_value`:
+///
+/// * integers into the `Nat` and `Int` variants of their width, `u128` and `i128` into `Nat` and `Int`,
+///   floats into `Float`, strings and chars into `Text`, bytes (e.g. with `serde_bytes`) into `Bytes`,
+/// * `None`, `()` and unit structs into `Option(None)`, `Some` into `Option(Some)`,
+/// * sequences, tuples and tuple structs into `Array`, newtype structs into their only field,
+/// * maps into a `Map` when all keys serialize into `Text`, into a `ValueMap` otherwise,
+/// * structs into a `Class` of mutable properties named after the fields,
+/// * enum variants into a `Class` tagged with the variant name, the shape `ic_candy_derive` and
+///   `idl::from_idl_value` give enums, holding the fields converted like a struct's or tuple's.
+///
+/// The format is not human readable, so that e.g. a `Principal` serializes into its `Bytes`.
+///
+/// ```
+/// use ic_candy::to_candy_value;
+/// use ic_candy::types::PropertyShared;
+/// use ic_candy::value::{CandyShared, ToCandyValue};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Listing {
+///     token_id: u64,
+///     price: Option<u128>,
+///     tags: Vec<String>,
+/// }
+///
+/// let listing = Listing { token_id: 1, price: None, tags: vec!["art".to_string()] };
+/// assert_eq!(
+///     to_candy_value(&listing),
+///     Ok(vec![
+///         PropertyShared { name: "token_id".to_string(), value: 1_u64.to_candy(), immutable: false },
+///         PropertyShared { name: "price".to_string(), value: CandyShared::Option(None), immutable: false },
+///         PropertyShared { name: "tags".to_string(), value: vec!["art".to_candy()].to_candy(), immutable: false },
+///     ]
+///     .to_candy())
+/// );
+/// ```
+pub fn to_candy_value<T>(value: &T) -> Result<CandyShared, CandyError>
+where
+    T: Serialize + ?Sized,
+{
+    value.serialize(Serializer)
+}
+
+/// The serde `Serializer` behind `to_candy_value`.
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = CandyShared;
+    type Error = CandyError;
+
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeTupleVariant;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeStruct;
+    type SerializeStructVariant = SerializeStructVariant;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Int8(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Int16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Int32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Int64(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Int(v.into()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Nat8(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Nat16(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Nat32(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Nat64(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Nat(v.into()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Float(v.into()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Text(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Text(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Option(None))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<CandyShared, CandyError>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(CandyShared::Option(Some(Box::new(to_candy_value(value)?))))
+    }
+
+    fn serialize_unit(self) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Option(None))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Option(None))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant_name: &'static str,
+    ) -> Result<CandyShared, CandyError> {
+        Ok(variant(variant_name, CandyShared::Option(None)))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<CandyShared, CandyError>
+    where
+        T: Serialize + ?Sized,
+    {
+        to_candy_value(value)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant_name: &'static str,
+        value: &T,
+    ) -> Result<CandyShared, CandyError>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(variant(variant_name, to_candy_value(value)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec, CandyError> {
+        Ok(SerializeVec {
+            items: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec, CandyError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec, CandyError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant_name: &'static str,
+        len: usize,
+    ) -> Result<SerializeTupleVariant, CandyError> {
+        Ok(SerializeTupleVariant {
+            name: variant_name,
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap, CandyError> {
+        Ok(SerializeMap {
+            entries: Vec::with_capacity(len.unwrap_or_default()),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeStruct, CandyError> {
+        Ok(SerializeStruct {
+            properties: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant_name: &'static str,
+        len: usize,
+    ) -> Result<SerializeStructVariant, CandyError> {
+        Ok(SerializeStructVariant {
+            name: variant_name,
+            properties: Vec::with_capacity(len),
+        })
+    }
+}
+
+/// Collects sequences, tuples and tuple structs into an `Array`.
+pub struct SerializeVec {
+    items: Vec<CandyShared>,
+}
+
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = CandyShared;
+    type Error = CandyError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), CandyError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.items.push(to_candy_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Array(self.items))
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = CandyShared;
+    type Error = CandyError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), CandyError>
+    where
+        T: Serialize + ?Sized,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<CandyShared, CandyError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = CandyShared;
+    type Error = CandyError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), CandyError>
+    where
+        T: Serialize + ?Sized,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<CandyShared, CandyError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Collects the fields of a tuple variant into a tagged `Array`.
+pub struct SerializeTupleVariant {
+    name: &'static str,
+    items: Vec<CandyShared>,
+}
+
+impl ser::SerializeTupleVariant for SerializeTupleVariant {
+    type Ok = CandyShared;
+    type Error = CandyError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), CandyError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.items.push(to_candy_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<CandyShared, CandyError> {
+        Ok(variant(self.name, CandyShared::Array(self.items)))
+    }
+}
+
+/// Collects map entries into a `Map` or `ValueMap`.
+pub struct SerializeMap {
+    entries: Vec<(CandyShared, CandyShared)>,
+    key: Option<CandyShared>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = CandyShared;
+    type Error = CandyError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), CandyError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.key = Some(to_candy_value(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), CandyError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| CandyError::Custom("map value without a key".to_string()))?;
+        self.entries.push((key, to_candy_value(value)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<CandyShared, CandyError> {
+        if self
+            .entries
+            .iter()
+            .all(|(key, _)| matches!(key, CandyShared::Text(_)))
+        {
+            let map = self
+                .entries
+                .into_iter()
+                .map(|(key, value)| match key {
+                    CandyShared::Text(key) => (key, value),
+                    _ => unreachable!("all keys are Text"),
+                })
+                .collect::<BTreeMap<_, _>>();
+            return Ok(CandyShared::Map(map));
+        }
+        Ok(CandyShared::ValueMap(self.entries.into_iter().collect()))
+    }
+}
+
+/// Collects the fields of a struct into a `Class`.
+pub struct SerializeStruct {
+    properties: Vec<PropertyShared>,
+}
+
+impl ser::SerializeStruct for SerializeStruct {
+    type Ok = CandyShared;
+    type Error = CandyError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), CandyError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.properties.push(property(key, value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<CandyShared, CandyError> {
+        Ok(CandyShared::Class(self.properties))
+    }
+}
+
+/// Collects the fields of a struct variant into a tagged `Class`.
+pub struct SerializeStructVariant {
+    name: &'static str,
+    properties: Vec<PropertyShared>,
+}
+
+impl ser::SerializeStructVariant for SerializeStructVariant {
+    type Ok = CandyShared;
+    type Error = CandyError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), CandyError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.properties.push(property(key, value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<CandyShared, CandyError> {
+        Ok(variant(self.name, CandyShared::Class(self.properties)))
+    }
+}
+
+fn property<T>(name: &str, value: &T) -> Result<PropertyShared, CandyError>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(PropertyShared {
+        name: name.to_string(),
+        value: to_candy_value(value)?,
+        immutable: false,
+    })
+}
@@ -1,19 +1,21 @@
+use std::fmt::Display;
 use std::hash::Hash;
 
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
 
-use crate::value::CandyShared;
+use crate::value::{CandyShared, CandyValue};
 
 pub type Properties = Vec<PropertyShared>;
 
-#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, CandidType, Serialize, Deserialize)]
 pub struct PropertyShared {
     pub name: String,
     pub value: CandyShared,
     pub immutable: bool,
 }
 
+#[allow(clippy::to_string_trait_impl, clippy::to_string_in_format_args)]
 impl ToString for PropertyShared {
     fn to_string(&self) -> String {
         let desc = match self.immutable {
@@ -24,7 +26,33 @@ impl ToString for PropertyShared {
     }
 }
 
+/// The mutable counterpart of `PropertyShared`, used by `CandyValue::Class`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub value: CandyValue,
+    pub immutable: bool,
+}
+
+impl Property {
+    pub fn share(self) -> PropertyShared {
+        PropertyShared {
+            name: self.name,
+            value: self.value.share(),
+            immutable: self.immutable,
+        }
+    }
+}
+
 impl PropertyShared {
+    pub fn unshare(self) -> Property {
+        Property {
+            name: self.name,
+            value: self.value.unshare(),
+            immutable: self.immutable,
+        }
+    }
+
     pub fn stringify_properties(props: &[PropertyShared]) -> String {
         let prop_strings: Vec<String> = props.iter().map(|p| p.to_string()).collect();
         format!("{{{}}}", prop_strings.join("").trim_end())
@@ -40,7 +68,7 @@ impl PropertyShared {
     }
 }
 
-#[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
 pub enum PropertyError {
     Unauthorized,
     NotFound,
@@ -49,17 +77,91 @@ pub enum PropertyError {
     Immutable,
 }
 
-#[derive(Clone, Debug, PartialOrd, CandidType, Serialize, Deserialize)]
+/// Errors produced by fallible conversions of `CandyShared` values.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum CandyError {
+    /// The variant (named in the payload) can not be used for the requested operation.
+    UnsupportedVariant(String),
+    /// A numeric value does not fit into the requested type, e.g. it is out of range or a non-integral `Float`.
+    Overflow,
+    /// The input bytes are not a valid encoding of the requested type.
+    InvalidEncoding(String),
+    /// The path (in the payload) can not be parsed.
+    InvalidPath(String),
+    /// The path (in the payload) does not point to an existing value.
+    PathNotFound(String),
+    /// The `Class` lacks the property (in the payload) a `FromCandy` type requires.
+    MissingField(String),
+    /// The property (in the payload) of a `Class` can not be converted into the field of a `FromCandy` type.
+    InvalidField(String, Box<CandyError>),
+    /// An error (in the payload) raised by a serde `Serialize` or `Deserialize` implementation.
+    Custom(String),
+}
+
+impl Display for CandyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedVariant(variant) => write!(f, "unsupported variant {}", variant),
+            Self::Overflow => write!(f, "numeric overflow"),
+            Self::InvalidEncoding(msg) => write!(f, "invalid encoding: {}", msg),
+            Self::InvalidPath(path) => write!(f, "invalid path {}", path),
+            Self::PathNotFound(path) => write!(f, "path {} not found", path),
+            Self::MissingField(field) => write!(f, "missing field {}", field),
+            Self::InvalidField(field, error) => write!(f, "invalid field {}: {}", field, error),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CandyError {}
+
+/// A request to read a property by name. When `next` is not empty the property
+/// must hold a `Class` and only the listed sub-properties are returned.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub struct Query {
+    pub name: String,
+    pub next: Vec<Query>,
+}
+
+/// A request to change a property by name.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub struct Update {
+    pub name: String,
+    pub mode: UpdateMode,
+}
+
+/// `Set` replaces (or creates) the property value, `Next` descends into a `Class` property
+/// and applies the nested updates to it.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum UpdateMode {
+    Set(CandyShared),
+    Next(Vec<Update>),
+}
+
+/// An `f64` with total equality: values are equal if their bits are, like `f64::total_cmp` orders them.
+#[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
 pub struct Float(f64);
 
 impl PartialEq for Float {
     fn eq(&self, other: &Self) -> bool {
-        (self.0 - other.0).abs() < f64::EPSILON
+        self.0.to_bits() == other.0.to_bits()
     }
 }
 
 impl Eq for Float {}
 
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Float {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
 impl Hash for Float {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.0.to_bits().hash(state);
@@ -1,20 +1,27 @@
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::Display;
 use std::hash::Hash;
 
-use candid::{CandidType, Deserialize, Encode, Principal};
+use candid::{CandidType, Deserialize, Encode, IDLValue, Principal};
 use hex::ToHex;
 use num_bigint::{BigInt, BigUint};
 use num_traits::cast::ToPrimitive;
-use num_traits::Signed;
+use num_traits::{Bounded, Signed, Zero};
 use serde::Serialize;
 
-use crate::types::PropertyShared;
+use crate::conversion;
+use crate::hashing;
+use crate::idl::{self, IdlError};
+use crate::json::{self, JsonOptions, JsonParseOptions};
+use crate::patch::{self, CandyPatch, PatchError};
+use crate::path::{self, CandyPath};
+use crate::types::{CandyError, Property, PropertyShared};
 
 /**
  * `CandyShared` is a Rust enum that provides a wrapper type for convenient data manipulation inside ICP canisters.
  * This enum includes various data types that can be used for communication between canisters, including integers, floats, text, boolean, and more.
- *
+ * `Map`, `ValueMap` and `Set` are sorted, so that iteration, hashing and serialization are the same on every replica.
  */
 #[derive(CandidType, Debug, Serialize, Deserialize, Clone)]
 pub enum CandyShared {
@@ -40,56 +47,151 @@ pub enum CandyShared {
     Array(Vec<CandyShared>),
     Nats(Vec<candid::Nat>),
     Floats(Vec<f64>),
-    Map(HashMap<String, CandyShared>),
-    ValueMap(HashMap<CandyShared, CandyShared>),
-    Set(HashSet<CandyShared>),
-}
-
-macro_rules! to_nat_of_size {
-    ($x:tt, $method: ident) => {
-        match $x {
-            Self::Nat(val) => val.0.$method(),
-            Self::Nat8(val) => val.$method(),
-            Self::Nat16(val) => val.$method(),
-            Self::Nat32(val) => val.$method(),
-            Self::Nat64(val) => val.$method(),
-            Self::Float(val) => match val {
-                val if val < 0.0 => None,
-                _ => val.round().$method(),
-            },
-            Self::Int(val) => val.0.$method(),
-            Self::Int8(val) => val.$method(),
-            Self::Int16(val) => val.$method(),
-            Self::Int32(val) => val.$method(),
-            Self::Int64(val) => val.$method(),
-            _ => None,
-        }
-    };
+    Map(BTreeMap<String, CandyShared>),
+    ValueMap(BTreeMap<CandyShared, CandyShared>),
+    Set(BTreeSet<CandyShared>),
 }
 
-macro_rules! to_int_of_size {
-    ($x:tt, $method: ident) => {
-        match $x {
-            Self::Nat(val) => val.0.$method(),
-            Self::Nat8(val) => val.$method(),
-            Self::Nat16(val) => val.$method(),
-            Self::Nat32(val) => val.$method(),
-            Self::Nat64(val) => val.$method(),
-            Self::Float(val) => val.round().$method(),
-            Self::Int(val) => val.0.$method(),
-            Self::Int8(val) => val.$method(),
-            Self::Int16(val) => val.$method(),
-            Self::Int32(val) => val.$method(),
-            Self::Int64(val) => val.$method(),
-            _ => None,
+/**
+ * `CandyValue` is the mutable counterpart of `CandyShared`, like Motoko's `Candy` is for `CandyShared`.
+ * Collections are kept in growable buffers and maps so that large values can be edited in place,
+ * `share` converts it into a `CandyShared` for inter-canister calls and `CandyShared::unshare` converts it back.
+ * `ValueMap` keys and `Set` members stay `CandyShared`, as they must not change while they are stored.
+ *
+ * # Examples
+ *
+ * ```
+ * use ic_candy::value::{CandyShared, CandyValue, ToCandyValue};
+ *
+ * let mut value = vec![1_u8.to_candy()].to_candy().unshare();
+ * if let CandyValue::Array(items) = &mut value {
+ *     items.push(CandyValue::Nat8(2));
+ * }
+ * assert_eq!(value.share(), vec![1_u8.to_candy(), 2_u8.to_candy()].to_candy());
+ * ```
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum CandyValue {
+    Int(candid::Int),
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    Ints(Vec<candid::Int>),
+    Nat(candid::Nat),
+    Nat8(u8),
+    Nat16(u16),
+    Nat32(u32),
+    Nat64(u64),
+    Float(f64),
+    Text(String),
+    Bool(bool),
+    Blob(Vec<u8>),
+    Bytes(Vec<u8>),
+    Class(Vec<Property>),
+    Principal(Principal),
+    Option(Option<Box<CandyValue>>),
+    Array(Vec<CandyValue>),
+    Nats(Vec<candid::Nat>),
+    Floats(Vec<f64>),
+    Map(BTreeMap<String, CandyValue>),
+    ValueMap(BTreeMap<CandyShared, CandyValue>),
+    Set(BTreeSet<CandyShared>),
+}
+
+impl CandyValue {
+    /// Converts the value into its shared counterpart.
+    pub fn share(self) -> CandyShared {
+        match self {
+            CandyValue::Int(val) => CandyShared::Int(val),
+            CandyValue::Int8(val) => CandyShared::Int8(val),
+            CandyValue::Int16(val) => CandyShared::Int16(val),
+            CandyValue::Int32(val) => CandyShared::Int32(val),
+            CandyValue::Int64(val) => CandyShared::Int64(val),
+            CandyValue::Ints(val) => CandyShared::Ints(val),
+            CandyValue::Nat(val) => CandyShared::Nat(val),
+            CandyValue::Nat8(val) => CandyShared::Nat8(val),
+            CandyValue::Nat16(val) => CandyShared::Nat16(val),
+            CandyValue::Nat32(val) => CandyShared::Nat32(val),
+            CandyValue::Nat64(val) => CandyShared::Nat64(val),
+            CandyValue::Float(val) => CandyShared::Float(val),
+            CandyValue::Text(val) => CandyShared::Text(val),
+            CandyValue::Bool(val) => CandyShared::Bool(val),
+            CandyValue::Blob(val) => CandyShared::Blob(val),
+            CandyValue::Bytes(val) => CandyShared::Bytes(val),
+            CandyValue::Class(val) => {
+                CandyShared::Class(val.into_iter().map(Property::share).collect())
+            }
+            CandyValue::Principal(val) => CandyShared::Principal(val),
+            CandyValue::Option(val) => CandyShared::Option(val.map(|val| Box::new(val.share()))),
+            CandyValue::Array(val) => {
+                CandyShared::Array(val.into_iter().map(CandyValue::share).collect())
+            }
+            CandyValue::Nats(val) => CandyShared::Nats(val),
+            CandyValue::Floats(val) => CandyShared::Floats(val),
+            CandyValue::Map(val) => CandyShared::Map(
+                val.into_iter()
+                    .map(|(key, val)| (key, val.share()))
+                    .collect(),
+            ),
+            CandyValue::ValueMap(val) => CandyShared::ValueMap(
+                val.into_iter()
+                    .map(|(key, val)| (key, val.share()))
+                    .collect(),
+            ),
+            CandyValue::Set(val) => CandyShared::Set(val),
         }
-    };
+    }
+}
+
+impl From<CandyShared> for CandyValue {
+    fn from(value: CandyShared) -> Self {
+        value.unshare()
+    }
+}
+
+impl From<CandyValue> for CandyShared {
+    fn from(value: CandyValue) -> Self {
+        value.share()
+    }
+}
+
+/// The `CandyShared` variant a blob should be decoded into by `CandyShared::from_blob`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlobTarget {
+    Int,
+    Nat,
+    Nat8,
+    Nat16,
+    Nat32,
+    Nat64,
+    Text,
+    Blob,
+    Bytes,
+    Principal,
+}
+
+/// How `CandyShared::to_nat_with`, `to_int_with` and their sized variants convert a number that the target type
+/// can not hold exactly: a non-integral `Float` or a number out of the target's range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NumericConversion {
+    /// Only integral values in range convert, so no precision is ever lost.
+    Strict,
+    /// Floats are rounded to the nearest integer, halfway cases away from zero, and negative floats never convert
+    /// to natural numbers. Values out of range do not convert. The mode of `to_nat`, `to_int` and their sized variants.
+    #[default]
+    Round,
+    /// Floats are truncated toward zero. Values out of range do not convert.
+    Truncate,
+    /// Floats are truncated toward zero and values out of range are clamped to the target's minimum or maximum,
+    /// like an `as` cast, except that `NaN` does not convert.
+    Saturate,
 }
 
 impl CandyShared {
     /**
      * `to_nat` is a method defined on the `CandyShared` Rust enum that provides a convenient way to convert a value to a natural number of size `u128`.
-     * This method uses the `to_nat_of_size!` macro to perform the conversion.
+     * Floats are rounded as `NumericConversion::Round` says, see `to_nat_strict` and `to_nat_with` for the other modes.
      *
      * # Examples
      *
@@ -102,12 +204,11 @@ impl CandyShared {
      * ```
      */
     pub fn to_nat(self) -> Option<u128> {
-        to_nat_of_size!(self, to_u128)
+        self.to_nat_with(NumericConversion::Round)
     }
 
     /**
      * `to_nat8` is a method defined on the `CandyShared` Rust enum that provides a convenient way to convert a value to an 8-bit natural number (`u8`).
-     * This method uses the `to_nat_of_size!` macro to perform the conversion.
      *
      * # Examples
      *
@@ -124,12 +225,11 @@ impl CandyShared {
      * ```
      */
     pub fn to_nat8(self) -> Option<u8> {
-        to_nat_of_size!(self, to_u8)
+        self.to_nat8_with(NumericConversion::Round)
     }
 
     /**
      * `to_nat16` is a method defined on the `CandyShared` Rust enum that provides a convenient way to convert a value to a natural number of size `u16`.
-     * This method uses the `to_nat_of_size!` macro to perform the conversion.
      *
      * # Examples
      *
@@ -142,12 +242,11 @@ impl CandyShared {
      * ```
      */
     pub fn to_nat16(self) -> Option<u16> {
-        to_nat_of_size!(self, to_u16)
+        self.to_nat16_with(NumericConversion::Round)
     }
 
     /**
      * `to_nat32` is a method defined on the `CandyShared` Rust enum that provides a convenient way to convert a value to a natural number of size `u32`.
-     * This method uses the `to_nat_of_size!` macro to perform the conversion.
      *
      * # Examples
      *
@@ -160,7 +259,7 @@ impl CandyShared {
      * ```
      */
     pub fn to_nat32(self) -> Option<u32> {
-        to_nat_of_size!(self, to_u32)
+        self.to_nat32_with(NumericConversion::Round)
     }
 
     /**
@@ -178,12 +277,12 @@ impl CandyShared {
      * ```
      */
     pub fn to_nat64(self) -> Option<u64> {
-        to_nat_of_size!(self, to_u64)
+        self.to_nat64_with(NumericConversion::Round)
     }
 
     /**
      * `to_int` is a method defined on the `CandyShared` Rust enum that provides a convenient way to convert a value to a signed integer of size `i128`.
-     * This method uses the `to_int_of_size!` macro to perform the conversion.
+     * Floats are rounded as `NumericConversion::Round` says, see `to_int_strict` and `to_int_with` for the other modes.
      *
      * # Examples
      *
@@ -196,7 +295,7 @@ impl CandyShared {
      * ```
      */
     pub fn to_int(self) -> Option<i128> {
-        to_int_of_size!(self, to_i128)
+        self.to_int_with(NumericConversion::Round)
     }
 
     /**
@@ -214,7 +313,7 @@ impl CandyShared {
      * ```
      */
     pub fn to_int8(self) -> Option<i8> {
-        to_int_of_size!(self, to_i8)
+        self.to_int8_with(NumericConversion::Round)
     }
 
     /**
@@ -236,7 +335,7 @@ impl CandyShared {
      * ```
      */
     pub fn to_int16(self) -> Option<i16> {
-        to_int_of_size!(self, to_i16)
+        self.to_int16_with(NumericConversion::Round)
     }
 
     /**
@@ -254,12 +353,11 @@ impl CandyShared {
      * ```
      */
     pub fn to_int32(self) -> Option<i32> {
-        to_int_of_size!(self, to_i32)
+        self.to_int32_with(NumericConversion::Round)
     }
 
     /**
      * `to_int64` is a method defined on the `CandyShared` Rust enum that provides a convenient way to convert a value to a signed integer of size `i64`.
-     * This method uses the `to_int_of_size!` macro to perform the conversion.
      *
      * # Examples
      *
@@ -272,7 +370,159 @@ impl CandyShared {
      * ```
      */
     pub fn to_int64(self) -> Option<i64> {
-        to_int_of_size!(self, to_i64)
+        self.to_int64_with(NumericConversion::Round)
+    }
+
+    /**
+     * `to_nat_strict` converts a value to a `u128` like `to_nat`, but only if no precision is lost:
+     * a `Float` must be integral, so that e.g. a ledger never accepts a rounded amount.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * assert_eq!(2.0_f64.to_candy().to_nat_strict(), Some(2));
+     * assert_eq!(1.6_f64.to_candy().to_nat_strict(), None);
+     * assert_eq!(1.6_f64.to_candy().to_nat(), Some(2));
+     * ```
+     */
+    pub fn to_nat_strict(self) -> Option<u128> {
+        self.to_nat_with(NumericConversion::Strict)
+    }
+
+    /**
+     * `to_int_strict` converts a value to an `i128` like `to_int`, but only if no precision is lost:
+     * a `Float` must be integral.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * assert_eq!((-2.0_f64).to_candy().to_int_strict(), Some(-2));
+     * assert_eq!((-1.5_f64).to_candy().to_int_strict(), None);
+     * ```
+     */
+    pub fn to_int_strict(self) -> Option<i128> {
+        self.to_int_with(NumericConversion::Strict)
+    }
+
+    /**
+     * `to_nat_with` converts a value to a `u128`, handling non-integral floats and numbers out of range
+     * as the given `NumericConversion` says. `to_nat8_with`, `to_nat16_with`, `to_nat32_with` and `to_nat64_with`
+     * do the same for the smaller types.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, NumericConversion, ToCandyValue};
+     *
+     * let value = 1.6_f64.to_candy();
+     * assert_eq!(value.clone().to_nat_with(NumericConversion::Strict), None);
+     * assert_eq!(value.clone().to_nat_with(NumericConversion::Round), Some(2));
+     * assert_eq!(value.to_nat_with(NumericConversion::Truncate), Some(1));
+     *
+     * let value = (-5_i8).to_candy();
+     * assert_eq!(value.clone().to_nat8_with(NumericConversion::Round), None);
+     * assert_eq!(value.to_nat8_with(NumericConversion::Saturate), Some(0));
+     * assert_eq!(300_u16.to_candy().to_nat8_with(NumericConversion::Saturate), Some(255));
+     * ```
+     */
+    pub fn to_nat_with(self, mode: NumericConversion) -> Option<u128> {
+        self.to_number(mode)
+    }
+
+    /// `to_nat8_with` converts a value to a `u8` like `to_nat_with` does.
+    pub fn to_nat8_with(self, mode: NumericConversion) -> Option<u8> {
+        self.to_number(mode)
+    }
+
+    /// `to_nat16_with` converts a value to a `u16` like `to_nat_with` does.
+    pub fn to_nat16_with(self, mode: NumericConversion) -> Option<u16> {
+        self.to_number(mode)
+    }
+
+    /// `to_nat32_with` converts a value to a `u32` like `to_nat_with` does.
+    pub fn to_nat32_with(self, mode: NumericConversion) -> Option<u32> {
+        self.to_number(mode)
+    }
+
+    /// `to_nat64_with` converts a value to a `u64` like `to_nat_with` does.
+    pub fn to_nat64_with(self, mode: NumericConversion) -> Option<u64> {
+        self.to_number(mode)
+    }
+
+    /**
+     * `to_int_with` converts a value to an `i128`, handling non-integral floats and numbers out of range
+     * as the given `NumericConversion` says. `to_int8_with`, `to_int16_with`, `to_int32_with` and `to_int64_with`
+     * do the same for the smaller types.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, NumericConversion, ToCandyValue};
+     *
+     * let value = (-2.5_f64).to_candy();
+     * assert_eq!(value.clone().to_int_with(NumericConversion::Strict), None);
+     * assert_eq!(value.clone().to_int_with(NumericConversion::Round), Some(-3));
+     * assert_eq!(value.to_int_with(NumericConversion::Truncate), Some(-2));
+     *
+     * let value = (-200_i64).to_candy();
+     * assert_eq!(value.clone().to_int8_with(NumericConversion::Truncate), None);
+     * assert_eq!(value.to_int8_with(NumericConversion::Saturate), Some(-128));
+     * ```
+     */
+    pub fn to_int_with(self, mode: NumericConversion) -> Option<i128> {
+        self.to_number(mode)
+    }
+
+    /// `to_int8_with` converts a value to an `i8` like `to_int_with` does.
+    pub fn to_int8_with(self, mode: NumericConversion) -> Option<i8> {
+        self.to_number(mode)
+    }
+
+    /// `to_int16_with` converts a value to an `i16` like `to_int_with` does.
+    pub fn to_int16_with(self, mode: NumericConversion) -> Option<i16> {
+        self.to_number(mode)
+    }
+
+    /// `to_int32_with` converts a value to an `i32` like `to_int_with` does.
+    pub fn to_int32_with(self, mode: NumericConversion) -> Option<i32> {
+        self.to_number(mode)
+    }
+
+    /// `to_int64_with` converts a value to an `i64` like `to_int_with` does.
+    pub fn to_int64_with(self, mode: NumericConversion) -> Option<i64> {
+        self.to_number(mode)
+    }
+
+    // Every target range holds zero, so a number that does not fit is below the minimum if it is negative
+    // and above the maximum otherwise.
+    fn to_number<T>(&self, mode: NumericConversion) -> Option<T>
+    where
+        T: Bounded + num_traits::NumCast + Zero,
+    {
+        let saturate = |negative: bool| match mode {
+            NumericConversion::Saturate if negative => Some(T::min_value()),
+            NumericConversion::Saturate => Some(T::max_value()),
+            _ => None,
+        };
+        if let Some(val) = self.as_float() {
+            let val = match mode {
+                NumericConversion::Strict if val.fract() != 0.0 => return None,
+                NumericConversion::Strict => val,
+                NumericConversion::Round if val < 0.0 && T::min_value().is_zero() => return None,
+                NumericConversion::Round => val.round(),
+                NumericConversion::Truncate | NumericConversion::Saturate => val.trunc(),
+            };
+            if val.is_nan() {
+                return None;
+            }
+            return T::from(val).or_else(|| saturate(val < 0.0));
+        }
+        let val = self.to_big_int()?;
+        T::from(val.clone()).or_else(|| saturate(val.is_negative()))
     }
 
     /**
@@ -306,6 +556,82 @@ impl CandyShared {
         }
     }
 
+    /**
+     * `numeric_cmp` compares the numbers held by two values regardless of their variant, e.g. `Nat8(5)` and `Int(5)`
+     * are equal numbers. Integers of any width and `Float` values are compared exactly.
+     * It returns `None` if either value is not a number or is `NaN`.
+     *
+     * # Examples
+     *
+     * ```
+     * use std::cmp::Ordering;
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * assert_eq!(5_u8.to_candy().numeric_cmp(&5_i128.to_candy()), Some(Ordering::Equal));
+     * assert_eq!((-1_i8).to_candy().numeric_cmp(&0.5_f64.to_candy()), Some(Ordering::Less));
+     * assert_eq!(5_u8.to_candy().numeric_cmp(&"5".to_candy()), None);
+     * ```
+     */
+    pub fn numeric_cmp(&self, other: &CandyShared) -> Option<Ordering> {
+        match (self.to_big_int(), other.to_big_int()) {
+            (Some(i1), Some(i2)) => Some(i1.cmp(&i2)),
+            (Some(i), None) => Self::cmp_int_float(&i, other.as_float()?),
+            (None, Some(i)) => Self::cmp_int_float(&i, self.as_float()?).map(Ordering::reverse),
+            (None, None) => self.as_float()?.partial_cmp(&other.as_float()?),
+        }
+    }
+
+    fn to_big_int(&self) -> Option<BigInt> {
+        match self {
+            Self::Nat(val) => Some(BigInt::from(val.0.clone())),
+            Self::Nat8(val) => Some(BigInt::from(*val)),
+            Self::Nat16(val) => Some(BigInt::from(*val)),
+            Self::Nat32(val) => Some(BigInt::from(*val)),
+            Self::Nat64(val) => Some(BigInt::from(*val)),
+            Self::Int(val) => Some(val.0.clone()),
+            Self::Int8(val) => Some(BigInt::from(*val)),
+            Self::Int16(val) => Some(BigInt::from(*val)),
+            Self::Int32(val) => Some(BigInt::from(*val)),
+            Self::Int64(val) => Some(BigInt::from(*val)),
+            _ => None,
+        }
+    }
+
+    // The error of a failed numeric conversion: an overflow for numbers, an unsupported variant otherwise.
+    fn number_error(&self) -> CandyError {
+        match self.to_big_int().is_some() || self.as_float().is_some() {
+            true => CandyError::Overflow,
+            false => CandyError::UnsupportedVariant(self.variant_name().to_string()),
+        }
+    }
+
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            Self::Float(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    // Compares without rounding the integer to a float: the integral parts are compared as integers first.
+    fn cmp_int_float(int: &BigInt, float: f64) -> Option<Ordering> {
+        if float.is_nan() {
+            return None;
+        }
+        if float.is_infinite() {
+            return Some(if float > 0.0 {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            });
+        }
+        let integral = num_traits::FromPrimitive::from_f64(float.trunc())?;
+        Some(int.cmp(&integral).then(match float.fract() {
+            fract if fract > 0.0 => Ordering::Less,
+            fract if fract < 0.0 => Ordering::Greater,
+            _ => Ordering::Equal,
+        }))
+    }
+
     /**
      * `to_bool` is a method defined on the `CandyShared` Rust enum that provides a convenient way to convert a value to a `bool` boolean.
      * This method returns an `Option<bool>` that contains the resulting `bool` value if the conversion is successful, and `None` otherwise.
@@ -350,9 +676,45 @@ impl CandyShared {
         }
     }
 
+    /**
+     * `try_to_blob` is a method defined on the `CandyShared` Rust enum that provides a convenient way to convert a value to a `Vec<u8>` blob.
+     * This method returns a `CandyError::UnsupportedVariant` for values that have no blob representation (Float, Array, Class, Map...).
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::types::CandyError;
+     * use ic_candy::value::{CandyShared,ToCandyValue};
+     *
+     * let value = 42_u8.to_candy();
+     * assert_eq!(value.try_to_blob(), Ok(vec![42]));
+     *
+     * let value = 4.2.to_candy();
+     * assert_eq!(value.try_to_blob(), Err(CandyError::UnsupportedVariant("Float".to_string())));
+     * ```
+     */
+    pub fn try_to_blob(self) -> Result<Vec<u8>, CandyError> {
+        match self {
+            Self::Blob(val) => Ok(val),
+            Self::Bytes(val) => Ok(val),
+            Self::Text(val) => Ok(val.chars().flat_map(|c| (c as u32).to_be_bytes()).collect()),
+            Self::Int(val) => Ok(val.0.to_blob()),
+            Self::Nat(val) => Ok(val.0.to_blob()),
+            Self::Nat8(val) => Ok([val].to_vec()),
+            Self::Nat16(val) => Ok(val.to_be_bytes().to_vec()),
+            Self::Nat32(val) => Ok(val.to_be_bytes().to_vec()),
+            Self::Nat64(val) => Ok(val.to_be_bytes().to_vec()),
+            Self::Principal(val) => Ok(val.as_slice().into()),
+            _ => Err(CandyError::UnsupportedVariant(
+                self.variant_name().to_string(),
+            )),
+        }
+    }
+
     /**
      * `to_blob` is a method defined on the `CandyShared` Rust enum that provides a convenient way to convert a value to a `Vec<u8>` blob.
-     * This method returns a `Vec<u8>` that contains the binary representation of the value.
+     * This method returns a `Vec<u8>` that contains the binary representation of the value and traps for unsupported variants,
+     * see `try_to_blob` for the fallible version. Only available with the `ic` feature.
      *
      * # Examples
      *
@@ -364,20 +726,41 @@ impl CandyShared {
      * assert_eq!(result, vec![0, 0, 0, 72, 0, 0, 0, 101, 0, 0, 0, 108, 0, 0, 0, 108, 0, 0, 0, 111, 0, 0, 0, 44, 0, 0, 0, 32, 0, 0, 0, 119, 0, 0, 0, 111, 0, 0, 0, 114, 0, 0, 0, 108, 0, 0, 0, 100, 0, 0, 0, 33]);
      * ```
      */
+    #[cfg(feature = "ic")]
     pub fn to_blob(self) -> Vec<u8> {
-        match self {
-            Self::Blob(val) => val,
-            Self::Bytes(val) => val,
-            Self::Text(val) => val.chars().flat_map(|c| (c as u32).to_be_bytes()).collect(),
-            Self::Int(val) => val.0.to_blob(),
-            Self::Nat(val) => val.0.to_blob(),
-            Self::Nat8(val) => [val].to_vec(),
-            Self::Nat16(val) => val.to_be_bytes().to_vec(),
-            Self::Nat32(val) => val.to_be_bytes().to_vec(),
-            Self::Nat64(val) => val.to_be_bytes().to_vec(),
-            Self::Principal(val) => val.as_slice().into(),
-            _ => ic_cdk::trap(format!("Cannot convert to blob {}", self).as_str()),
-        }
+        self.try_to_blob()
+            .unwrap_or_else(|e| ic_cdk::trap(format!("Cannot convert to blob: {}", e).as_str()))
+    }
+
+    /**
+     * `from_blob` is the inverse of `to_blob`: it decodes a `Vec<u8>` blob into the `CandyShared` variant given by `target`.
+     * This method returns a `CandyError::InvalidEncoding` instead of panicking when the blob is malformed.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{BlobTarget, CandyShared, ToCandyValue};
+     *
+     * let blob = (-123_i128).to_candy().try_to_blob().unwrap();
+     * let result = CandyShared::from_blob(&blob, BlobTarget::Int);
+     * assert_eq!(result, Ok((-123_i128).to_candy()));
+     *
+     * assert!(CandyShared::from_blob(&[0, 0, 0], BlobTarget::Text).is_err());
+     * ```
+     */
+    pub fn from_blob(blob: &[u8], target: BlobTarget) -> Result<CandyShared, CandyError> {
+        Ok(match target {
+            BlobTarget::Int => Self::Int(candid::Int(conversion::blob_to_int(blob)?)),
+            BlobTarget::Nat => Self::Nat(candid::Nat(conversion::blob_to_nat(blob)?)),
+            BlobTarget::Nat8 => Self::Nat8(conversion::blob_to_nat8(blob)?),
+            BlobTarget::Nat16 => Self::Nat16(conversion::blob_to_nat16(blob)?),
+            BlobTarget::Nat32 => Self::Nat32(conversion::blob_to_nat32(blob)?),
+            BlobTarget::Nat64 => Self::Nat64(conversion::blob_to_nat64(blob)?),
+            BlobTarget::Text => Self::Text(conversion::blob_to_text(blob)?),
+            BlobTarget::Blob => Self::Blob(blob.to_vec()),
+            BlobTarget::Bytes => Self::Bytes(blob.to_vec()),
+            BlobTarget::Principal => Self::Principal(conversion::blob_to_principal(blob)?),
+        })
     }
 
     /**
@@ -445,87 +828,759 @@ impl CandyShared {
             Self::Blob(val) => format!("\"{}\"", val.encode_hex::<String>()),
             Self::Principal(val) => format!("\"{}\"", val),
             Self::Bool(val) => format!("\"{}\"", val),
-            _ => "".to_string(),
+            Self::Map(val) => Self::entries_to_json(
+                val.into_iter()
+                    .map(|(key, value)| (serde_json::to_string(&key).unwrap(), value.to_json()))
+                    .collect(),
+            ),
+            Self::ValueMap(val) => {
+                if val.keys().all(|key| matches!(key, Self::Text(_))) {
+                    Self::entries_to_json(
+                        val.into_iter()
+                            .map(|(key, value)| (key.to_json(), value.to_json()))
+                            .collect(),
+                    )
+                } else {
+                    let pairs: Vec<String> = val
+                        .into_iter()
+                        .map(|(key, value)| format!("[{},{}]", key.to_json(), value.to_json()))
+                        .collect();
+                    format!("[{}]", pairs.join(","))
+                }
+            }
+            Self::Set(val) => {
+                let items: Vec<String> = val.into_iter().map(|i| i.to_json()).collect();
+                format!("[{}]", items.join(","))
+            }
         }
     }
 
+    // Renders already encoded key/value pairs as a JSON object
+    fn entries_to_json(entries: Vec<(String, String)>) -> String {
+        format!(
+            "{{{}}}",
+            entries
+                .into_iter()
+                .map(|(key, value)| format!("{}:{}", key, value))
+                .collect::<Vec<String>>()
+                .join(",")
+        )
+    }
+
     /**
-     * `to_value_array` is a method defined on the `CandyShared` Rust enum that provides a convenient way to convert a value to a `Vec<CandyShared>` array.
-     * This method returns an `Option<Vec<CandyShared>>` that contains the resulting array if the conversion is successful, and `None` otherwise.
+     * `to_json_with` converts a value to a JSON string using the given `JsonOptions`: pretty printing,
+     * big numbers as strings, native booleans, hex or base64 binary data and the policy for non-finite floats.
+     * Unlike `to_json` it borrows the value and always produces valid JSON, Map, ValueMap and Set entries are sorted.
      *
      * # Examples
      *
      * ```
-     * use ic_candy::value::{CandyShared,ToCandyValue};
+     * use ic_candy::json::{BinaryEncoding, JsonOptions};
+     * use ic_candy::value::{CandyShared, ToCandyValue};
      *
-     * let arr = vec![42_u128.to_candy(), 50_u128.to_candy()];
-     * let value = CandyShared::Array(arr);
-     * let result = value.to_value_array();
-     * assert_eq!(result, Some(vec![42_u128.to_candy(), 50_u128.to_candy()]));
+     * let value = vec![u64::MAX.to_candy(), true.to_candy(), vec![1_u8, 2_u8].to_candy()].to_candy();
+     * let options = JsonOptions::new()
+     *     .big_numbers_as_strings(1 << 53)
+     *     .native_bools(true)
+     *     .binary_encoding(BinaryEncoding::Base64);
+     * assert_eq!(value.to_json_with(&options), r#"["18446744073709551615",true,"AQI="]"#);
+     *
+     * let options = JsonOptions::new().indent(2);
+     * assert_eq!(vec![1_u8.to_candy()].to_candy().to_json_with(&options), "[\n  1\n]");
      * ```
      */
-    pub fn to_value_array(self) -> Option<Vec<CandyShared>> {
-        match self {
-            Self::Array(val) => Some(val),
-            _ => None,
-        }
+    pub fn to_json_with(&self, options: &JsonOptions) -> String {
+        json::to_json_with(self, options)
     }
 
-    // Return the size of the value in bytes
-    ///
-    /// ```
-    /// use ic_candy::value::CandyShared;
-    /// use ic_candy::workspace::DataZone;
-    /// use crate::ic_candy::workspace::DataZoneTrait;
-    /// use crate::ic_candy::value::ToCandyValue;
-    /// use candid::Principal;
-    ///
-    ///
-    /// let dz : CandyShared = vec![0_u8;3_000_000].to_candy();
-    /// assert_eq!(dz.get_value_size(),3_000_210);
-    /// ```
-    pub fn get_value_size(&self) -> u128 {
-        Encode!(self).unwrap().len() as u128
+    /**
+     * `write_json` streams the JSON representation of the value into `out` without cloning or building intermediate strings
+     * for nested values. The output is the same as `to_json_with(&JsonOptions::default())`.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let value = vec![1_u8.to_candy(), "a".to_candy()].to_candy();
+     * let mut out = String::new();
+     * value.write_json(&mut out).unwrap();
+     * assert_eq!(out, r#"[1,"a"]"#);
+     * ```
+     */
+    pub fn write_json<W: std::fmt::Write>(&self, out: &mut W) -> std::fmt::Result {
+        json::write_json_with(self, &JsonOptions::default(), out)
     }
 
-    pub fn stringify_array_of_values(vals: &[CandyShared]) -> String {
-        let mut result = String::new();
-        result.push('[');
-        for value in vals {
-            let converted = format!("{{{}}} ", value.clone());
-            result.push_str(&converted);
-        }
-        let mut trimmed = result.trim_end().to_string();
-        trimmed.push(']');
-        trimmed
+    /**
+     * `write_json_with` streams the JSON representation of the value into `out` using the given `JsonOptions`.
+     * Wrap `out` in a `json::LimitedWriter` to stop writing at a byte limit, which allows rendering large values
+     * inside a bounded query response.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::json::{JsonOptions, LimitedWriter};
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let value = vec![true.to_candy(), "some long text".to_candy()].to_candy();
+     * let mut out = String::new();
+     * let mut limited = LimitedWriter::new(&mut out, 12);
+     * let result = value.write_json_with(&JsonOptions::new().native_bools(true), &mut limited);
+     * assert!(result.is_err() && limited.limit_reached());
+     * assert_eq!(out, r#"[true,"some "#);
+     * ```
+     */
+    pub fn write_json_with<W: std::fmt::Write>(
+        &self,
+        options: &JsonOptions,
+        out: &mut W,
+    ) -> std::fmt::Result {
+        json::write_json_with(self, options, out)
     }
 
-    fn are_maps_equal<T: Eq + Hash>(
-        map1: &HashMap<T, CandyShared>,
-        map2: &HashMap<T, CandyShared>,
-    ) -> bool {
-        if map1.len() != map2.len() {
-            false
-        } else {
-            for (key1, value1) in map1.iter() {
-                match map2.get(key1) {
-                    Some(value2) => {
-                        if !(value1 == value2) {
-                            return false;
-                        }
-                    }
-                    None => return false,
-                }
-            }
-            true
-        }
+    /**
+     * `from_json` is the counterpart of `to_json`: it parses a JSON string into a `CandyShared` value.
+     * Objects become `Class` (keeping key order, all properties mutable), arrays become `Array`, non-negative integers `Nat`,
+     * negative integers `Int`, other numbers `Float` and `null` becomes `Option(None)`.
+     * See `from_json_with` to parse typed arrays and hex blobs.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let value = CandyShared::from_json("[1, -2, null]").unwrap();
+     * assert_eq!(value, vec![1_u128.to_candy(), (-2_i128).to_candy(), CandyShared::Option(None)].to_candy());
+     * ```
+     */
+    pub fn from_json(json: &str) -> Result<CandyShared, CandyError> {
+        json::parse(json, &JsonParseOptions::default())
     }
-}
 
-macro_rules! impl_from {
-    ($($t:ty => $v:ident),*) => {
-        $(impl From<$t> for CandyShared {
-            fn from(value: $t) -> Self {
+    /**
+     * `from_json_with` parses a JSON string into a `CandyShared` value using the given `JsonParseOptions`.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::json::JsonParseOptions;
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let options = JsonParseOptions { typed_arrays: true, hex_as_blob: true };
+     * let value = CandyShared::from_json_with("[1, 2]", &options).unwrap();
+     * assert_eq!(value, vec![1_u128, 2_u128].to_candy());
+     *
+     * let value = CandyShared::from_json_with("\"0a0b\"", &options).unwrap();
+     * assert_eq!(value, vec![10_u8, 11_u8].to_candy());
+     * ```
+     */
+    pub fn from_json_with(
+        json: &str,
+        options: &JsonParseOptions,
+    ) -> Result<CandyShared, CandyError> {
+        json::parse(json, options)
+    }
+
+    /**
+     * `to_tagged_json` is a lossless alternative to `to_json`: every value is wrapped in an object named after its variant,
+     * e.g. `{"Nat64":"123"}`. Nat, Int, Nat64 and Int64 are written as strings so that JavaScript clients keep full precision,
+     * Blob and Bytes as hex strings, non-finite floats as `"NaN"`, `"Infinity"` and `"-Infinity"`.
+     * Map, ValueMap and Set entries are sorted so the output is stable. Use `from_tagged_json` to restore the value.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let value = 123_u64.to_candy();
+     * assert_eq!(value.to_tagged_json(), r#"{"Nat64":"123"}"#);
+     *
+     * let value = vec![Some(Box::new(true.to_candy())).to_candy(), 1.5.to_candy()].to_candy();
+     * assert_eq!(value.to_tagged_json(), r#"{"Array":[{"Option":{"Bool":true}},{"Float":1.5}]}"#);
+     * ```
+     */
+    pub fn to_tagged_json(&self) -> String {
+        json::to_tagged_json(self)
+    }
+
+    /**
+     * `from_tagged_json` parses the output of `to_tagged_json` back into the exact same `CandyShared` value.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let value = CandyShared::Bytes(vec![1, 2]);
+     * assert_eq!(CandyShared::from_tagged_json(&value.to_tagged_json()), Ok(value));
+     *
+     * assert!(CandyShared::from_tagged_json(r#"{"Nat8":256}"#).is_err());
+     * ```
+     */
+    pub fn from_tagged_json(json: &str) -> Result<CandyShared, CandyError> {
+        json::parse_tagged(json)
+    }
+
+    /**
+     * `to_value_array` is a method defined on the `CandyShared` Rust enum that provides a convenient way to convert a value to a `Vec<CandyShared>` array.
+     * Like Motoko's `candySharedToValueArray` it returns the items of an `Array` or `Set` and widens the typed arrays
+     * `Nats`, `Ints` and `Floats` into `Nat`, `Int` and `Float` items. It returns `None` for any other variant.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared,ToCandyValue};
+     *
+     * let arr = vec![42_u128.to_candy(), 50_u128.to_candy()];
+     * let value = CandyShared::Array(arr);
+     * let result = value.to_value_array();
+     * assert_eq!(result, Some(vec![42_u128.to_candy(), 50_u128.to_candy()]));
+     *
+     * let value = CandyShared::Nats(vec![42_u8.into(), 50_u8.into()]);
+     * assert_eq!(value.to_value_array(), Some(vec![42_u128.to_candy(), 50_u128.to_candy()]));
+     * assert_eq!(42_u128.to_candy().to_value_array(), None);
+     * ```
+     */
+    pub fn to_value_array(self) -> Option<Vec<CandyShared>> {
+        match self {
+            Self::Array(val) => Some(val),
+            Self::Set(val) => Some(val.into_iter().collect()),
+            Self::Nats(val) => Some(val.into_iter().map(Self::Nat).collect()),
+            Self::Ints(val) => Some(val.into_iter().map(Self::Int).collect()),
+            Self::Floats(val) => Some(val.into_iter().map(Self::Float).collect()),
+            _ => None,
+        }
+    }
+
+    /**
+     * `to_text` converts any value into text the way Motoko's `candySharedToText` does: numbers and booleans
+     * in decimal, `Text` as is, `Blob` and `Bytes` in hex, `Principal` in its textual form, `Option(None)` as `null`
+     * and collections in the bracketed forms of the `Display` implementation, which it uses.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * assert_eq!("candy".to_candy().to_text(), "candy");
+     * assert_eq!((-42_i8).to_candy().to_text(), "-42");
+     * assert_eq!(CandyShared::Bytes(vec![1, 255]).to_text(), "01ff");
+     * assert_eq!(CandyShared::Option(None).to_text(), "null");
+     * ```
+     */
+    pub fn to_text(self) -> String {
+        self.to_string()
+    }
+
+    /**
+     * `to_bytes` converts a value into its bytes like Motoko's `candySharedToBytes`, the same bytes `try_to_blob`
+     * returns. It returns `None` for variants without a binary representation.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * assert_eq!(258_u16.to_candy().to_bytes(), Some(vec![1, 2]));
+     * assert_eq!(CandyShared::Blob(vec![7]).to_bytes(), Some(vec![7]));
+     * assert_eq!(1.5_f64.to_candy().to_bytes(), None);
+     * ```
+     */
+    pub fn to_bytes(self) -> Option<Vec<u8>> {
+        self.try_to_blob().ok()
+    }
+
+    /**
+     * `to_nats` converts a collection of numbers into `u128` values, reading `Nats` as well as the items of an
+     * `Array` or `Set` and the bytes of a `Blob` or `Bytes`. Every item converts like `to_nat` does,
+     * so the method returns `None` if any of them is negative, too large or not a number.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * assert_eq!(vec![1_u128, 2].to_candy().to_nats(), Some(vec![1, 2]));
+     * assert_eq!(vec![1_u8.to_candy(), 2_i64.to_candy()].to_candy().to_nats(), Some(vec![1, 2]));
+     * assert_eq!(vec![(-1_i8).to_candy()].to_candy().to_nats(), None);
+     * ```
+     */
+    pub fn to_nats(self) -> Option<Vec<u128>> {
+        self.into_number_array()?
+            .into_iter()
+            .map(Self::to_nat)
+            .collect()
+    }
+
+    /**
+     * `to_ints` converts a collection of numbers into `i128` values, reading `Ints` as well as the collections
+     * `to_nats` reads. Every item converts like `to_int` does.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * assert_eq!(vec![-1_i128, 2].to_candy().to_ints(), Some(vec![-1, 2]));
+     * assert_eq!(vec![1_u128, 2].to_candy().to_ints(), Some(vec![1, 2]));
+     * assert_eq!("1".to_candy().to_ints(), None);
+     * ```
+     */
+    pub fn to_ints(self) -> Option<Vec<i128>> {
+        self.into_number_array()?
+            .into_iter()
+            .map(Self::to_int)
+            .collect()
+    }
+
+    /**
+     * `to_floats` converts a collection of numbers into `f64` values, reading `Floats` as well as the collections
+     * `to_nats` reads. Every item converts like `to_float` does.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * assert_eq!(vec![0.5_f64, 2.0].to_candy().to_floats(), Some(vec![0.5, 2.0]));
+     * assert_eq!(vec![-1_i128].to_candy().to_floats(), Some(vec![-1.0]));
+     * assert_eq!(vec![true.to_candy()].to_candy().to_floats(), None);
+     * ```
+     */
+    pub fn to_floats(self) -> Option<Vec<f64>> {
+        self.into_number_array()?
+            .into_iter()
+            .map(Self::to_float)
+            .collect()
+    }
+
+    // The items `to_nats`, `to_ints` and `to_floats` convert.
+    fn into_number_array(self) -> Option<Vec<CandyShared>> {
+        match self {
+            Self::Blob(val) | Self::Bytes(val) => Some(val.into_iter().map(Self::Nat8).collect()),
+            _ => self.to_value_array(),
+        }
+    }
+
+    /**
+     * `to_class` returns the properties of a `Class` like Motoko's `candySharedToProperties`, and `None` for any
+     * other variant.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::types::PropertyShared;
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let props = vec![PropertyShared { name: "a".to_string(), value: 1_u8.to_candy(), immutable: true }];
+     * assert_eq!(props.clone().to_candy().to_class(), Some(props));
+     * assert_eq!(1_u8.to_candy().to_class(), None);
+     * ```
+     */
+    pub fn to_class(self) -> Option<Vec<PropertyShared>> {
+        match self {
+            Self::Class(val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /**
+     * `to_map` returns the entries of a `Map`. It also reads a `ValueMap` whose keys are all `Text` and a `Class`,
+     * keyed by property name, where a later property replaces an earlier one of the same name.
+     * It returns `None` for any other value.
+     *
+     * # Examples
+     *
+     * ```
+     * use std::collections::{BTreeMap, HashMap};
+     * use ic_candy::types::PropertyShared;
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let mut map = HashMap::new();
+     * map.insert("a".to_string(), 1_u8.to_candy());
+     * let expected = BTreeMap::from([("a".to_string(), 1_u8.to_candy())]);
+     * assert_eq!(map.to_candy().to_map(), Some(expected.clone()));
+     *
+     * let class = vec![PropertyShared { name: "a".to_string(), value: 1_u8.to_candy(), immutable: false }];
+     * assert_eq!(class.to_candy().to_map(), Some(expected));
+     *
+     * let mut value_map = HashMap::new();
+     * value_map.insert(1_u8.to_candy(), 1_u8.to_candy());
+     * assert_eq!(value_map.to_candy().to_map(), None);
+     * ```
+     */
+    pub fn to_map(self) -> Option<BTreeMap<String, CandyShared>> {
+        match self {
+            Self::Map(val) => Some(val),
+            Self::ValueMap(val) => val
+                .into_iter()
+                .map(|(key, val)| match key {
+                    Self::Text(key) => Some((key, val)),
+                    _ => None,
+                })
+                .collect(),
+            Self::Class(val) => Some(
+                val.into_iter()
+                    .map(|prop| (prop.name, prop.value))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /**
+     * `to_set` returns the members of a `Set`, or collects the items `to_value_array` returns into a set,
+     * dropping duplicates.
+     *
+     * # Examples
+     *
+     * ```
+     * use std::collections::BTreeSet;
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let value = vec![1_u8.to_candy(), 1_u8.to_candy(), 2_u8.to_candy()].to_candy();
+     * assert_eq!(value.to_set(), Some(BTreeSet::from([1_u8.to_candy(), 2_u8.to_candy()])));
+     * assert_eq!("a".to_candy().to_set(), None);
+     * ```
+     */
+    pub fn to_set(self) -> Option<BTreeSet<CandyShared>> {
+        match self {
+            Self::Set(val) => Some(val),
+            _ => self.to_value_array().map(|val| val.into_iter().collect()),
+        }
+    }
+
+    /**
+     * `get` returns a reference to the value at the given `CandyPath`, looking up `Class` properties, `Map` keys,
+     * `Text` keys of a `ValueMap` and `Array` indices, and unwrapping `Option(Some(..))` on the way.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::types::PropertyShared;
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let value = vec![PropertyShared {
+     *     name: "attributes".to_string(),
+     *     value: vec![1_u8.to_candy(), 2_u8.to_candy()].to_candy(),
+     *     immutable: false,
+     * }]
+     * .to_candy();
+     * assert_eq!(value.get(&"attributes[1]".parse().unwrap()), Some(&2_u8.to_candy()));
+     * assert_eq!(value.get(&"attributes[2]".parse().unwrap()), None);
+     * ```
+     */
+    pub fn get(&self, path: &CandyPath) -> Option<&CandyShared> {
+        path::get(self, path)
+    }
+
+    /**
+     * `get_mut` returns a mutable reference to the value at the given `CandyPath`, see `get`.
+     * Note that it does not check the `immutable` flag of `Class` properties.
+     */
+    pub fn get_mut(&mut self, path: &CandyPath) -> Option<&mut CandyShared> {
+        path::get_mut(self, path)
+    }
+
+    /**
+     * `set` stores `value` at the given `CandyPath` and returns the previous value, if any.
+     * Missing `Class` properties (created mutable) and map keys are added, an `Array` index equal to the length appends
+     * and a `Set` member path replaces that member with `value`.
+     * This method returns a `CandyError::PathNotFound` if the parent of the path does not exist.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let mut value = CandyShared::Class(vec![]);
+     * assert_eq!(value.set(&"name".parse().unwrap(), "candy".to_candy()), Ok(None));
+     * assert_eq!(value.get(&"name".parse().unwrap()), Some(&"candy".to_candy()));
+     * assert!(value.set(&"missing.name".parse().unwrap(), "candy".to_candy()).is_err());
+     * ```
+     */
+    pub fn set(
+        &mut self,
+        path: &CandyPath,
+        value: CandyShared,
+    ) -> Result<Option<CandyShared>, CandyError> {
+        path::set(self, path, value)
+    }
+
+    /**
+     * `remove` removes the value at the given `CandyPath` and returns it, `None` if there is no such value.
+     * The root value can not be removed.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let mut value = vec![1_u8.to_candy(), 2_u8.to_candy()].to_candy();
+     * assert_eq!(value.remove(&"[0]".parse().unwrap()), Some(1_u8.to_candy()));
+     * assert_eq!(value, vec![2_u8.to_candy()].to_candy());
+     * ```
+     */
+    pub fn remove(&mut self, path: &CandyPath) -> Option<CandyShared> {
+        path::remove(self, path)
+    }
+
+    /**
+     * `apply_patch` applies JSON-Patch-style operations in order, either all of them or none.
+     * Operations changing an immutable `Class` property or anything below it are refused.
+     * See `patch::apply_patch` for details.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::patch::CandyPatch;
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let mut value = vec![1_u8.to_candy(), 3_u8.to_candy()].to_candy();
+     * value
+     *     .apply_patch(&[
+     *         CandyPatch::Add { path: "[1]".parse().unwrap(), value: 2_u8.to_candy() },
+     *         CandyPatch::Test { path: "[2]".parse().unwrap(), value: 3_u8.to_candy() },
+     *     ])
+     *     .unwrap();
+     * assert_eq!(value, vec![1_u8.to_candy(), 2_u8.to_candy(), 3_u8.to_candy()].to_candy());
+     * ```
+     */
+    pub fn apply_patch(&mut self, patches: &[CandyPatch]) -> Result<(), PatchError> {
+        patch::apply_patch(self, patches)
+    }
+
+    /**
+     * `unshare` converts the value into its mutable counterpart `CandyValue`.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, CandyValue, ToCandyValue};
+     *
+     * let value = vec![1_u8.to_candy()].to_candy();
+     * assert_eq!(value.clone().unshare().share(), value);
+     * ```
+     */
+    pub fn unshare(self) -> CandyValue {
+        match self {
+            CandyShared::Int(val) => CandyValue::Int(val),
+            CandyShared::Int8(val) => CandyValue::Int8(val),
+            CandyShared::Int16(val) => CandyValue::Int16(val),
+            CandyShared::Int32(val) => CandyValue::Int32(val),
+            CandyShared::Int64(val) => CandyValue::Int64(val),
+            CandyShared::Ints(val) => CandyValue::Ints(val),
+            CandyShared::Nat(val) => CandyValue::Nat(val),
+            CandyShared::Nat8(val) => CandyValue::Nat8(val),
+            CandyShared::Nat16(val) => CandyValue::Nat16(val),
+            CandyShared::Nat32(val) => CandyValue::Nat32(val),
+            CandyShared::Nat64(val) => CandyValue::Nat64(val),
+            CandyShared::Float(val) => CandyValue::Float(val),
+            CandyShared::Text(val) => CandyValue::Text(val),
+            CandyShared::Bool(val) => CandyValue::Bool(val),
+            CandyShared::Blob(val) => CandyValue::Blob(val),
+            CandyShared::Bytes(val) => CandyValue::Bytes(val),
+            CandyShared::Class(val) => {
+                CandyValue::Class(val.into_iter().map(PropertyShared::unshare).collect())
+            }
+            CandyShared::Principal(val) => CandyValue::Principal(val),
+            CandyShared::Option(val) => CandyValue::Option(val.map(|val| Box::new(val.unshare()))),
+            CandyShared::Array(val) => {
+                CandyValue::Array(val.into_iter().map(CandyShared::unshare).collect())
+            }
+            CandyShared::Nats(val) => CandyValue::Nats(val),
+            CandyShared::Floats(val) => CandyValue::Floats(val),
+            CandyShared::Map(val) => CandyValue::Map(
+                val.into_iter()
+                    .map(|(key, val)| (key, val.unshare()))
+                    .collect(),
+            ),
+            CandyShared::ValueMap(val) => CandyValue::ValueMap(
+                val.into_iter()
+                    .map(|(key, val)| (key, val.unshare()))
+                    .collect(),
+            ),
+            CandyShared::Set(val) => CandyValue::Set(val),
+        }
+    }
+
+    /**
+     * `hash_sha256` returns the ICRC-3 representation-independent hash of the value, which unlike `Hash`
+     * is stable across versions and platforms and can be used for certification.
+     * See `hashing::hash_sha256` for how each variant is hashed.
+     *
+     * # Examples
+     *
+     * ```
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * assert_eq!(
+     *     hex::encode("Hello, World!".to_candy().hash_sha256()),
+     *     "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"
+     * );
+     * ```
+     */
+    pub fn hash_sha256(&self) -> [u8; 32] {
+        hashing::hash_sha256(self)
+    }
+
+    /**
+     * `from_idl_value` converts a dynamically typed Candid value, e.g. an argument decoded into `IDLArgs`,
+     * into a `CandyShared`. Records become a `Class` and variants a `Class` tagged with the variant label.
+     * Function and service references can not be converted. See `idl::from_idl_value` for details.
+     *
+     * # Examples
+     *
+     * ```
+     * use candid::{IDLArgs, IDLValue};
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let args = IDLArgs::from_bytes(&candid::encode_args((vec![1_u8, 2], Some("candy"))).unwrap()).unwrap();
+     * // The decoder reads `vec nat8` as a blob.
+     * assert_eq!(CandyShared::from_idl_value(args.args[0].clone()), Ok(CandyShared::Blob(vec![1, 2])));
+     * assert_eq!(
+     *     CandyShared::from_idl_value(args.args[1].clone()),
+     *     Ok(CandyShared::Option(Some(Box::new("candy".to_candy()))))
+     * );
+     * ```
+     */
+    pub fn from_idl_value(value: IDLValue) -> Result<CandyShared, IdlError> {
+        idl::from_idl_value(value)
+    }
+
+    /**
+     * `to_idl_value` converts the value into a dynamically typed Candid value, reversing `from_idl_value`.
+     * See `idl::to_idl_value` for details.
+     *
+     * # Examples
+     *
+     * ```
+     * use candid::IDLArgs;
+     * use ic_candy::value::{CandyShared, ToCandyValue};
+     *
+     * let value = vec![1_u128, 2].to_candy();
+     * let args = IDLArgs::new(&[value.to_idl_value().unwrap()]);
+     * let decoded = IDLArgs::from_bytes(&args.to_bytes().unwrap()).unwrap();
+     * assert_eq!(CandyShared::from_idl_value(decoded.args[0].clone()), Ok(value));
+     * ```
+     */
+    pub fn to_idl_value(&self) -> Result<IDLValue, IdlError> {
+        idl::to_idl_value(self)
+    }
+
+    /// Returns the name of the variant, e.g. `"Nat64"` for `CandyShared::Nat64(..)`.
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            Self::Int(_) => "Int",
+            Self::Int8(_) => "Int8",
+            Self::Int16(_) => "Int16",
+            Self::Int32(_) => "Int32",
+            Self::Int64(_) => "Int64",
+            Self::Ints(_) => "Ints",
+            Self::Nat(_) => "Nat",
+            Self::Nat8(_) => "Nat8",
+            Self::Nat16(_) => "Nat16",
+            Self::Nat32(_) => "Nat32",
+            Self::Nat64(_) => "Nat64",
+            Self::Float(_) => "Float",
+            Self::Text(_) => "Text",
+            Self::Bool(_) => "Bool",
+            Self::Blob(_) => "Blob",
+            Self::Bytes(_) => "Bytes",
+            Self::Class(_) => "Class",
+            Self::Principal(_) => "Principal",
+            Self::Option(_) => "Option",
+            Self::Array(_) => "Array",
+            Self::Nats(_) => "Nats",
+            Self::Floats(_) => "Floats",
+            Self::Map(_) => "Map",
+            Self::ValueMap(_) => "ValueMap",
+            Self::Set(_) => "Set",
+        }
+    }
+
+    // Whether the value is, or contains, a `Class` with an immutable property, which replacing or removing
+    // the whole value would drop.
+    pub(crate) fn holds_immutable(&self) -> bool {
+        match self {
+            Self::Class(props) => props
+                .iter()
+                .any(|prop| prop.immutable || prop.value.holds_immutable()),
+            Self::Option(Some(value)) => value.holds_immutable(),
+            Self::Array(items) => items.iter().any(Self::holds_immutable),
+            Self::Map(map) => map.values().any(Self::holds_immutable),
+            Self::ValueMap(map) => map.values().any(Self::holds_immutable),
+            Self::Set(items) => items.iter().any(Self::holds_immutable),
+            _ => false,
+        }
+    }
+
+    // The position of the variant in the declaration order, used to order values of different variants.
+    fn variant_rank(&self) -> u8 {
+        match self {
+            Self::Int(_) => 0,
+            Self::Int8(_) => 1,
+            Self::Int16(_) => 2,
+            Self::Int32(_) => 3,
+            Self::Int64(_) => 4,
+            Self::Ints(_) => 5,
+            Self::Nat(_) => 6,
+            Self::Nat8(_) => 7,
+            Self::Nat16(_) => 8,
+            Self::Nat32(_) => 9,
+            Self::Nat64(_) => 10,
+            Self::Float(_) => 11,
+            Self::Text(_) => 12,
+            Self::Bool(_) => 13,
+            Self::Blob(_) => 14,
+            Self::Bytes(_) => 15,
+            Self::Class(_) => 16,
+            Self::Principal(_) => 17,
+            Self::Option(_) => 18,
+            Self::Array(_) => 19,
+            Self::Nats(_) => 20,
+            Self::Floats(_) => 21,
+            Self::Map(_) => 22,
+            Self::ValueMap(_) => 23,
+            Self::Set(_) => 24,
+        }
+    }
+
+    // Return the size of the value in bytes
+    ///
+    /// ```
+    /// use ic_candy::value::CandyShared;
+    /// use ic_candy::workspace::DataZone;
+    /// use crate::ic_candy::workspace::DataZoneTrait;
+    /// use crate::ic_candy::value::ToCandyValue;
+    /// use candid::Principal;
+    ///
+    ///
+    /// let dz : CandyShared = vec![0_u8;3_000_000].to_candy();
+    /// assert_eq!(dz.get_value_size(),3_000_210);
+    /// ```
+    pub fn get_value_size(&self) -> u128 {
+        Encode!(self).unwrap().len() as u128
+    }
+
+    pub fn stringify_array_of_values(vals: &[CandyShared]) -> String {
+        let mut result = String::new();
+        result.push('[');
+        for value in vals {
+            let converted = format!("{{{}}} ", value.clone());
+            result.push_str(&converted);
+        }
+        let mut trimmed = result.trim_end().to_string();
+        trimmed.push(']');
+        trimmed
+    }
+}
+
+macro_rules! impl_from {
+    ($($t:ty => $v:ident),*) => {
+        $(impl From<$t> for CandyShared {
+            fn from(value: $t) -> Self {
                 CandyShared::$v(value)
             }
         })*
@@ -551,11 +1606,29 @@ impl_from!(
     Principal => Principal ,
     Option<Box<CandyShared >> => Option,
     Vec<u8> => Blob,
-    HashMap<CandyShared,CandyShared> => ValueMap,
-    HashMap<String,CandyShared> => Map,
-    HashSet<CandyShared> => Set
+    BTreeMap<CandyShared,CandyShared> => ValueMap,
+    BTreeMap<String,CandyShared> => Map,
+    BTreeSet<CandyShared> => Set
 );
 
+impl From<HashMap<CandyShared, CandyShared>> for CandyShared {
+    fn from(value: HashMap<CandyShared, CandyShared>) -> Self {
+        CandyShared::ValueMap(value.into_iter().collect())
+    }
+}
+
+impl From<HashMap<String, CandyShared>> for CandyShared {
+    fn from(value: HashMap<String, CandyShared>) -> Self {
+        CandyShared::Map(value.into_iter().collect())
+    }
+}
+
+impl From<HashSet<CandyShared>> for CandyShared {
+    fn from(value: HashSet<CandyShared>) -> Self {
+        CandyShared::Set(value.into_iter().collect())
+    }
+}
+
 impl From<u128> for CandyShared {
     fn from(value: u128) -> Self {
         CandyShared::Nat(candid::Nat::from(value))
@@ -622,6 +1695,182 @@ impl From<Vec<f64>> for CandyShared {
     }
 }
 
+impl From<candid::Nat> for CandyShared {
+    fn from(value: candid::Nat) -> Self {
+        CandyShared::Nat(value)
+    }
+}
+
+impl From<candid::Int> for CandyShared {
+    fn from(value: candid::Int) -> Self {
+        CandyShared::Int(value)
+    }
+}
+
+// The reverse conversions, used for the fields of `#[derive(FromCandy)]` types.
+// Integers convert from any numeric variant like `to_nat8_with`, `to_int_with` etc. do with `NumericConversion::Strict`,
+// so a non-integral `Float` is refused rather than rounded, and fail with `CandyError::Overflow` if they do not fit.
+// Other types convert only from the variant they convert into.
+macro_rules! try_from_number {
+    ($($t:ty => $method:ident),*) => {
+        $(impl TryFrom<CandyShared> for $t {
+            type Error = CandyError;
+
+            fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+                let error = value.number_error();
+                value.$method(NumericConversion::Strict).ok_or(error)
+            }
+        })*
+    };
+}
+
+try_from_number!(
+    u8 => to_nat8_with,
+    u16 => to_nat16_with,
+    u32 => to_nat32_with,
+    u64 => to_nat64_with,
+    u128 => to_nat_with,
+    i8 => to_int8_with,
+    i16 => to_int16_with,
+    i32 => to_int32_with,
+    i64 => to_int64_with,
+    i128 => to_int_with
+);
+
+impl TryFrom<CandyShared> for f64 {
+    type Error = CandyError;
+
+    fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+        let error = value.number_error();
+        value.to_float().ok_or(error)
+    }
+}
+
+macro_rules! try_from_variant {
+    ($($t:ty => $v:ident),*) => {
+        $(impl TryFrom<CandyShared> for $t {
+            type Error = CandyError;
+
+            fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+                match value {
+                    CandyShared::$v(val) => Ok(val),
+                    _ => Err(CandyError::UnsupportedVariant(value.variant_name().to_string())),
+                }
+            }
+        })*
+    };
+}
+
+try_from_variant!(
+    String => Text,
+    bool => Bool,
+    Vec<PropertyShared> => Class,
+    Principal => Principal,
+    Option<Box<CandyShared>> => Option,
+    Vec<CandyShared> => Array,
+    Vec<candid::Nat> => Nats,
+    Vec<candid::Int> => Ints,
+    Vec<f64> => Floats,
+    BTreeMap<CandyShared, CandyShared> => ValueMap,
+    BTreeMap<String, CandyShared> => Map,
+    BTreeSet<CandyShared> => Set
+);
+
+impl TryFrom<CandyShared> for BigInt {
+    type Error = CandyError;
+
+    fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+        value
+            .to_big_int()
+            .ok_or_else(|| CandyError::UnsupportedVariant(value.variant_name().to_string()))
+    }
+}
+
+impl TryFrom<CandyShared> for BigUint {
+    type Error = CandyError;
+
+    fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+        BigInt::try_from(value)?
+            .to_biguint()
+            .ok_or(CandyError::Overflow)
+    }
+}
+
+impl TryFrom<CandyShared> for candid::Int {
+    type Error = CandyError;
+
+    fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+        BigInt::try_from(value).map(candid::Int)
+    }
+}
+
+impl TryFrom<CandyShared> for candid::Nat {
+    type Error = CandyError;
+
+    fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+        BigUint::try_from(value).map(candid::Nat)
+    }
+}
+
+impl TryFrom<CandyShared> for Vec<u8> {
+    type Error = CandyError;
+
+    fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+        match value {
+            CandyShared::Blob(val) | CandyShared::Bytes(val) => Ok(val),
+            _ => Err(CandyError::UnsupportedVariant(
+                value.variant_name().to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<CandyShared> for Vec<u128> {
+    type Error = CandyError;
+
+    fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+        Vec::<candid::Nat>::try_from(value)?
+            .into_iter()
+            .map(|val| val.0.to_u128().ok_or(CandyError::Overflow))
+            .collect()
+    }
+}
+
+impl TryFrom<CandyShared> for Vec<i128> {
+    type Error = CandyError;
+
+    fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+        Vec::<candid::Int>::try_from(value)?
+            .into_iter()
+            .map(|val| val.0.to_i128().ok_or(CandyError::Overflow))
+            .collect()
+    }
+}
+
+impl TryFrom<CandyShared> for HashMap<CandyShared, CandyShared> {
+    type Error = CandyError;
+
+    fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+        BTreeMap::<CandyShared, CandyShared>::try_from(value).map(|val| val.into_iter().collect())
+    }
+}
+
+impl TryFrom<CandyShared> for HashMap<String, CandyShared> {
+    type Error = CandyError;
+
+    fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+        BTreeMap::<String, CandyShared>::try_from(value).map(|val| val.into_iter().collect())
+    }
+}
+
+impl TryFrom<CandyShared> for HashSet<CandyShared> {
+    type Error = CandyError;
+
+    fn try_from(value: CandyShared) -> Result<Self, Self::Error> {
+        BTreeSet::<CandyShared>::try_from(value).map(|val| val.into_iter().collect())
+    }
+}
+
 /**
  * `ToCandyValue` is a trait that defines a method `to_candy`, which is used to convert a value of any type into a `CandyShared` enum.
  * This trait can be implemented by any type that can be converted into a `CandyShared` enum, allowing for seamless integration with the `CandyShared` type in Rust code.
@@ -667,6 +1916,8 @@ to_candy!(
     u32,
     u64,
     f64,
+    candid::Int,
+    candid::Nat,
     Vec<u128>,
     Vec<i128>,
     Vec<f64>,
@@ -680,9 +1931,273 @@ to_candy!(
     &str,
     HashMap<CandyShared, CandyShared>,
     HashMap<String, CandyShared>,
-    HashSet<CandyShared>
+    HashSet<CandyShared>,
+    BTreeMap<CandyShared, CandyShared>,
+    BTreeMap<String, CandyShared>,
+    BTreeSet<CandyShared>
+);
+
+/**
+ * `FromCandyValue` is the reverse of `ToCandyValue`: it converts a `CandyShared` into a value of the implementing type,
+ * so that generic code can decode nested candy into typed Rust with `T::from_candy(value)`.
+ * It is implemented for the types `ToCandyValue` covers, converting integers like `to_nat8_with`, `to_int_with` etc. do
+ * with `NumericConversion::Strict`, so that a non-integral `Float` is never rounded into an amount,
+ * and for `Vec<T>`, `Option<T>`, `Box<T>`, hash and B-tree maps and sets, tuples, `BigUint` and `BigInt`.
+ * Conversions fail with `CandyError::UnsupportedVariant` for a variant the type can not be read from and
+ * `CandyError::Overflow` for a number that does not fit exactly.
+ *
+ * # Examples
+ *
+ * ```
+ * use std::collections::HashMap;
+ * use ic_candy::types::CandyError;
+ * use ic_candy::value::{CandyShared, FromCandyValue, ToCandyValue};
+ *
+ * let value = vec![vec![1_u8, 2].to_candy(), CandyShared::Bytes(vec![3])].to_candy();
+ * assert_eq!(Vec::<Vec<u16>>::from_candy(value), Ok(vec![vec![1, 2], vec![3]]));
+ *
+ * let value = vec![1_u8.to_candy(), "a".to_candy()].to_candy();
+ * assert_eq!(<(u8, String)>::from_candy(value.clone()), Ok((1, "a".to_string())));
+ * assert_eq!(<(u8, Option<String>)>::from_candy(value), Err(CandyError::UnsupportedVariant("Text".to_string())));
+ *
+ * let mut map = HashMap::new();
+ * map.insert("a".to_string(), 300_u16.to_candy());
+ * assert_eq!(HashMap::<String, u8>::from_candy(map.to_candy()), Err(CandyError::Overflow));
+ * ```
+ */
+pub trait FromCandyValue: Sized {
+    fn from_candy(value: CandyShared) -> Result<Self, CandyError>;
+
+    /// Converts a collection into a `Vec<Self>`, used by the implementation for `Vec<T>`.
+    /// The items of `Array` and `Set` values, the numbers of `Nats`, `Ints` and `Floats` and the bytes
+    /// of `Blob` and `Bytes`, as `Nat8` values, are converted one by one.
+    fn from_candy_vec(value: CandyShared) -> Result<Vec<Self>, CandyError> {
+        let items: Vec<CandyShared> = match value {
+            CandyShared::Array(val) => val,
+            CandyShared::Set(val) => val.into_iter().collect(),
+            CandyShared::Nats(val) => val.into_iter().map(CandyShared::Nat).collect(),
+            CandyShared::Ints(val) => val.into_iter().map(CandyShared::Int).collect(),
+            CandyShared::Floats(val) => val.into_iter().map(CandyShared::Float).collect(),
+            CandyShared::Blob(val) | CandyShared::Bytes(val) => {
+                val.into_iter().map(CandyShared::Nat8).collect()
+            }
+            _ => {
+                return Err(CandyError::UnsupportedVariant(
+                    value.variant_name().to_string(),
+                ))
+            }
+        };
+        items.into_iter().map(Self::from_candy).collect()
+    }
+}
+
+macro_rules! from_candy {
+    ($($t:ty),*) => {
+        $(impl FromCandyValue for $t {
+            /// Converts the given `CandyShared` like `TryFrom<CandyShared>` does.
+            #[inline]
+            fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+                Self::try_from(value)
+            }
+        })*
+    };
+}
+
+from_candy!(
+    BigInt,
+    BigUint,
+    candid::Int,
+    candid::Nat,
+    i128,
+    i8,
+    i16,
+    i32,
+    i64,
+    u128,
+    u16,
+    u32,
+    u64,
+    f64,
+    String,
+    bool,
+    Principal
 );
 
+impl FromCandyValue for u8 {
+    fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+        Self::try_from(value)
+    }
+
+    fn from_candy_vec(value: CandyShared) -> Result<Vec<Self>, CandyError> {
+        match value {
+            CandyShared::Blob(val) | CandyShared::Bytes(val) => Ok(val),
+            CandyShared::Array(val) => val.into_iter().map(Self::from_candy).collect(),
+            CandyShared::Set(val) => val.into_iter().map(Self::from_candy).collect(),
+            CandyShared::Nats(val) => val
+                .into_iter()
+                .map(|val| Self::from_candy(CandyShared::Nat(val)))
+                .collect(),
+            CandyShared::Ints(val) => val
+                .into_iter()
+                .map(|val| Self::from_candy(CandyShared::Int(val)))
+                .collect(),
+            CandyShared::Floats(val) => val
+                .into_iter()
+                .map(|val| Self::from_candy(CandyShared::Float(val)))
+                .collect(),
+            _ => Err(CandyError::UnsupportedVariant(
+                value.variant_name().to_string(),
+            )),
+        }
+    }
+}
+
+impl FromCandyValue for CandyShared {
+    #[inline]
+    fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+        Ok(value)
+    }
+}
+
+impl FromCandyValue for PropertyShared {
+    /// Reads the only property of a `Class`.
+    fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+        match value {
+            CandyShared::Class(mut val) if val.len() == 1 => Ok(val.remove(0)),
+            _ => Err(CandyError::UnsupportedVariant(
+                value.variant_name().to_string(),
+            )),
+        }
+    }
+
+    /// Reads the properties of a `Class`.
+    fn from_candy_vec(value: CandyShared) -> Result<Vec<Self>, CandyError> {
+        Vec::<PropertyShared>::try_from(value)
+    }
+}
+
+impl<T: FromCandyValue> FromCandyValue for Vec<T> {
+    fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+        T::from_candy_vec(value)
+    }
+}
+
+impl<T: FromCandyValue> FromCandyValue for Box<T> {
+    fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+        T::from_candy(value).map(Box::new)
+    }
+}
+
+impl<T: FromCandyValue> FromCandyValue for Option<T> {
+    /// Reads an `Option`, `None` for `Option(None)`.
+    fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+        match value {
+            CandyShared::Option(val) => val.map(|val| T::from_candy(*val)).transpose(),
+            _ => Err(CandyError::UnsupportedVariant(
+                value.variant_name().to_string(),
+            )),
+        }
+    }
+}
+
+// The entries of a `Map`, `ValueMap` or `Class`, with `Text` keys for `Map` keys and property names.
+fn map_entries<K, V>(value: CandyShared) -> Result<Vec<(K, V)>, CandyError>
+where
+    K: FromCandyValue,
+    V: FromCandyValue,
+{
+    let entries: Vec<(CandyShared, CandyShared)> = match value {
+        CandyShared::Map(val) => val
+            .into_iter()
+            .map(|(key, val)| (CandyShared::Text(key), val))
+            .collect(),
+        CandyShared::ValueMap(val) => val.into_iter().collect(),
+        CandyShared::Class(val) => val
+            .into_iter()
+            .map(|prop| (CandyShared::Text(prop.name), prop.value))
+            .collect(),
+        _ => {
+            return Err(CandyError::UnsupportedVariant(
+                value.variant_name().to_string(),
+            ))
+        }
+    };
+    entries
+        .into_iter()
+        .map(|(key, val)| Ok((K::from_candy(key)?, V::from_candy(val)?)))
+        .collect()
+}
+
+impl<K, V> FromCandyValue for HashMap<K, V>
+where
+    K: FromCandyValue + Eq + Hash,
+    V: FromCandyValue,
+{
+    /// Reads a `Map`, `ValueMap` or `Class`, converting `Map` keys and property names from `Text`.
+    fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+        map_entries(value).map(|entries| entries.into_iter().collect())
+    }
+}
+
+impl<K, V> FromCandyValue for BTreeMap<K, V>
+where
+    K: FromCandyValue + Ord,
+    V: FromCandyValue,
+{
+    /// Reads a `Map`, `ValueMap` or `Class`, converting `Map` keys and property names from `Text`.
+    fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+        map_entries(value).map(|entries| entries.into_iter().collect())
+    }
+}
+
+impl<T: FromCandyValue + Eq + Hash> FromCandyValue for HashSet<T> {
+    /// Reads the collections `Vec<T>` reads.
+    fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+        T::from_candy_vec(value).map(|items| items.into_iter().collect())
+    }
+}
+
+impl<T: FromCandyValue + Ord> FromCandyValue for BTreeSet<T> {
+    /// Reads the collections `Vec<T>` reads.
+    fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+        T::from_candy_vec(value).map(|items| items.into_iter().collect())
+    }
+}
+
+macro_rules! from_candy_tuple {
+    ($len:expr => $($t:ident),+) => {
+        impl<$($t: FromCandyValue),+> FromCandyValue for ($($t,)+) {
+            /// Reads an `Array` of as many items as the tuple has, or a `Class` with the properties `"0"`, `"1"`, ...
+            /// like a Candid tuple converted by `from_idl_value`.
+            fn from_candy(value: CandyShared) -> Result<Self, CandyError> {
+                let items: Vec<CandyShared> = match value {
+                    CandyShared::Array(val) if val.len() == $len => val,
+                    CandyShared::Class(val)
+                        if val.len() == $len
+                            && val.iter().enumerate().all(|(index, prop)| prop.name == index.to_string()) =>
+                    {
+                        val.into_iter().map(|prop| prop.value).collect()
+                    }
+                    _ => {
+                        return Err(CandyError::UnsupportedVariant(
+                            value.variant_name().to_string(),
+                        ))
+                    }
+                };
+                let mut items = items.into_iter();
+                Ok(($($t::from_candy(items.next().expect("length checked above"))?,)+))
+            }
+        }
+    };
+}
+
+from_candy_tuple!(1 => A);
+from_candy_tuple!(2 => A, B);
+from_candy_tuple!(3 => A, B, C);
+from_candy_tuple!(4 => A, B, C, D);
+from_candy_tuple!(5 => A, B, C, D, E);
+from_candy_tuple!(6 => A, B, C, D, E, F);
+
 /**
  * `ToBlob` is a trait that is implemented by types that can be converted to a `Vec<u8>` blob.
  *
@@ -693,9 +2208,9 @@ to_candy!(
  * ```
  * use crate::ic_candy::value::{CandyShared, ToBlob, ToCandyValue};
  *
- * let value = 42_u128.to_candy();
+ * let value = num_bigint::BigUint::from(256_u32);
  * let result = value.to_blob();
- * assert_eq!(result, vec![42]);
+ * assert_eq!(result, vec![1, 0]);
  * ```
  */
 pub trait ToBlob {
@@ -703,43 +2218,19 @@ pub trait ToBlob {
 }
 
 impl ToBlob for BigUint {
+    /// Big-endian base-256 digits, `[0]` for zero.
     #[inline]
     fn to_blob(self) -> Vec<u8> {
-        let mut b = self;
-        let mut bytes: Vec<u8> = Vec::new();
-        loop {
-            let a = (b.clone() % BigUint::from(256_u32))
-                .to_u8()
-                .unwrap_or_else(|| ic_cdk::trap("Can not convert BigUint to u8"));
-            b /= BigUint::from(256_u32);
-            bytes.push(a);
-            if b == BigUint::from(0_u32) {
-                break;
-            }
-        }
-        bytes.reverse();
-        bytes
+        self.to_bytes_be()
     }
 }
 
 impl ToBlob for BigInt {
+    /// A sign byte (`1` for negative numbers) followed by the big-endian base-128 digits of the magnitude.
     #[inline]
     fn to_blob(self) -> Vec<u8> {
-        let c = u8::from(self < BigInt::from(0_i32));
-        let mut b = self.abs();
-        let mut bytes: Vec<u8> = vec![];
-        loop {
-            let a = (b.clone() % BigInt::from(128_i32))
-                .to_u8()
-                .unwrap_or_else(|| ic_cdk::trap("Can not convert BigInt to u8"));
-            b /= BigInt::from(128_i32);
-            bytes.push(a);
-            if b == BigInt::from(0_i32) {
-                break;
-            }
-        }
-        bytes.reverse();
-        bytes.insert(0, c);
+        let mut bytes = vec![u8::from(self.is_negative())];
+        bytes.append(&mut self.magnitude().to_radix_be(128));
         bytes
     }
 }
@@ -751,8 +2242,11 @@ impl ToBlob for char {
     }
 }
 
+/// Hashing is consistent with `Eq`: floats are hashed by their bits, like `f64::total_cmp` compares them,
+/// and `Map`, `ValueMap` and `Set` are hashed in their sorted order, so equal values always hash the same.
 impl Hash for CandyShared {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.variant_rank().hash(state);
         match self {
             CandyShared::Int(i) => i.hash(state),
             CandyShared::Int8(i) => i.hash(state),
@@ -764,103 +2258,113 @@ impl Hash for CandyShared {
             CandyShared::Nat16(i) => i.hash(state),
             CandyShared::Nat32(i) => i.hash(state),
             CandyShared::Nat64(i) => i.hash(state),
-            CandyShared::Float(i) => i.to_string().hash(state),
+            CandyShared::Float(i) => i.to_bits().hash(state),
             CandyShared::Text(i) => i.hash(state),
             CandyShared::Bool(i) => i.hash(state),
             CandyShared::Blob(i) => i.hash(state),
             CandyShared::Bytes(i) => i.hash(state),
-            CandyShared::Class(i) => {
-                for prop in i {
-                    prop.name.hash(state);
-                    prop.value.hash(state);
-                    prop.immutable.hash(state);
-                }
-            }
+            CandyShared::Class(i) => i.hash(state),
             CandyShared::Principal(i) => i.hash(state),
             CandyShared::Option(i) => i.hash(state),
-            CandyShared::Array(i) => {
-                for prop in i {
-                    prop.hash(state)
-                }
-            }
+            CandyShared::Array(i) => i.hash(state),
             CandyShared::Nats(i) => i.hash(state),
             CandyShared::Ints(i) => i.hash(state),
             CandyShared::Floats(i) => {
-                let mut buffer: Vec<u8> = Vec::new();
+                i.len().hash(state);
                 for num in i {
-                    buffer.append(num.to_string().into_bytes().as_mut());
-                }
-                buffer.hash(state)
-            }
-            CandyShared::Map(map) => {
-                for (key, value) in map {
-                    key.hash(state);
-                    value.hash(state);
-                }
-            }
-            CandyShared::ValueMap(map) => {
-                for (key, value) in map {
-                    key.hash(state);
-                    value.hash(state);
-                }
-            }
-            CandyShared::Set(i) => {
-                for prop in i {
-                    prop.hash(state)
+                    num.to_bits().hash(state);
                 }
             }
+            CandyShared::Map(map) => map.hash(state),
+            CandyShared::ValueMap(map) => map.hash(state),
+            CandyShared::Set(i) => i.hash(state),
         }
     }
 }
 
 impl PartialEq for CandyShared {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (CandyShared::Int(i1), CandyShared::Int(i2)) => i1 == i2,
-            (CandyShared::Int8(i1), CandyShared::Int8(i2)) => i1 == i2,
-            (CandyShared::Int16(i1), CandyShared::Int16(i2)) => i1 == i2,
-            (CandyShared::Int32(i1), CandyShared::Int32(i2)) => i1 == i2,
-            (CandyShared::Int64(i1), CandyShared::Int64(i2)) => i1 == i2,
-            (CandyShared::Nat(i1), CandyShared::Nat(i2)) => i1 == i2,
-            (CandyShared::Nat8(i1), CandyShared::Nat8(i2)) => i1 == i2,
-            (CandyShared::Nat16(i1), CandyShared::Nat16(i2)) => i1 == i2,
-            (CandyShared::Nat32(i1), CandyShared::Nat32(i2)) => i1 == i2,
-            (CandyShared::Nat64(i1), CandyShared::Nat64(i2)) => i1 == i2,
-            (CandyShared::Float(i1), CandyShared::Float(i2)) => i1 == i2,
-            (CandyShared::Text(i1), CandyShared::Text(i2)) => i1 == i2,
-            (CandyShared::Bool(i1), CandyShared::Bool(i2)) => i1 == i2,
-            (CandyShared::Blob(i1), CandyShared::Blob(i2)) => i1 == i2,
-            (CandyShared::Bytes(i1), CandyShared::Bytes(i2)) => i1 == i2,
-            (CandyShared::Class(i1), CandyShared::Class(i2)) => i1 == i2,
-            (CandyShared::Principal(i1), CandyShared::Principal(i2)) => i1 == i2,
-            (CandyShared::Option(i1), CandyShared::Option(i2)) => i1 == i2,
-            (CandyShared::Array(i1), CandyShared::Array(i2)) => i1 == i2,
-            (CandyShared::Nats(i1), CandyShared::Nats(i2)) => i1 == i2,
-            (CandyShared::Ints(i1), CandyShared::Ints(i2)) => i1 == i2,
-            (CandyShared::Floats(i1), CandyShared::Floats(i2)) => i1 == i2,
-            (CandyShared::Map(map1), CandyShared::Map(map2)) => Self::are_maps_equal(map1, map2),
-            (CandyShared::ValueMap(map1), CandyShared::ValueMap(map2)) => {
-                Self::are_maps_equal(map1, map2)
-            }
-            (CandyShared::Set(set1), CandyShared::Set(set2)) => {
-                if set1.len() != set2.len() {
-                    false
-                } else {
-                    for element in set1 {
-                        if !set2.contains(element) {
-                            return false;
-                        }
-                    }
-                    true
-                }
-            }
-            _ => false,
-        }
+        self.cmp(other) == Ordering::Equal
     }
 }
 
 impl Eq for CandyShared {}
 
+impl PartialOrd for CandyShared {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/**
+ * A total order over all values, consistent with `Eq` and `Hash`, so values can be sorted and used as
+ * `BTreeMap` keys.
+ *
+ * Values of different variants are ordered by the variant declaration order (`Int` < `Int8` < ... < `Set`),
+ * even if they hold the same number; use `CandyShared::numeric_cmp` to compare numbers across variants.
+ * Values of the same variant are compared by content:
+ *
+ * * integers numerically, `Float` values with `f64::total_cmp` (`-NaN` < `-inf` < `-0.0` < `0.0` < `inf` < `NaN`),
+ * * `Text`, `Blob` and `Bytes` lexicographically, `false` before `true`, `Principal` like `candid::Principal`,
+ * * `Array`, `Ints`, `Nats` and `Floats` element by element, a prefix before the longer value,
+ * * `Class` property by property, comparing name, value and `immutable` flag in that order,
+ * * `Option(None)` before any `Option(Some(..))`,
+ * * `Map`, `ValueMap` and `Set` entry by entry in their sorted order.
+ *
+ * # Examples
+ *
+ * ```
+ * use ic_candy::value::{CandyShared, ToCandyValue};
+ *
+ * let mut values = vec!["b".to_candy(), 2_u8.to_candy(), "a".to_candy(), 1_u8.to_candy(), 3_i8.to_candy()];
+ * values.sort();
+ * assert_eq!(
+ *     values,
+ *     vec![3_i8.to_candy(), 1_u8.to_candy(), 2_u8.to_candy(), "a".to_candy(), "b".to_candy()]
+ * );
+ * ```
+ */
+impl Ord for CandyShared {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (CandyShared::Int(i1), CandyShared::Int(i2)) => i1.cmp(i2),
+            (CandyShared::Int8(i1), CandyShared::Int8(i2)) => i1.cmp(i2),
+            (CandyShared::Int16(i1), CandyShared::Int16(i2)) => i1.cmp(i2),
+            (CandyShared::Int32(i1), CandyShared::Int32(i2)) => i1.cmp(i2),
+            (CandyShared::Int64(i1), CandyShared::Int64(i2)) => i1.cmp(i2),
+            (CandyShared::Ints(i1), CandyShared::Ints(i2)) => i1.cmp(i2),
+            (CandyShared::Nat(i1), CandyShared::Nat(i2)) => i1.cmp(i2),
+            (CandyShared::Nat8(i1), CandyShared::Nat8(i2)) => i1.cmp(i2),
+            (CandyShared::Nat16(i1), CandyShared::Nat16(i2)) => i1.cmp(i2),
+            (CandyShared::Nat32(i1), CandyShared::Nat32(i2)) => i1.cmp(i2),
+            (CandyShared::Nat64(i1), CandyShared::Nat64(i2)) => i1.cmp(i2),
+            (CandyShared::Float(i1), CandyShared::Float(i2)) => i1.total_cmp(i2),
+            (CandyShared::Text(i1), CandyShared::Text(i2)) => i1.cmp(i2),
+            (CandyShared::Bool(i1), CandyShared::Bool(i2)) => i1.cmp(i2),
+            (CandyShared::Blob(i1), CandyShared::Blob(i2)) => i1.cmp(i2),
+            (CandyShared::Bytes(i1), CandyShared::Bytes(i2)) => i1.cmp(i2),
+            (CandyShared::Class(i1), CandyShared::Class(i2)) => i1
+                .iter()
+                .map(|p| (&p.name, &p.value, p.immutable))
+                .cmp(i2.iter().map(|p| (&p.name, &p.value, p.immutable))),
+            (CandyShared::Principal(i1), CandyShared::Principal(i2)) => i1.cmp(i2),
+            (CandyShared::Option(i1), CandyShared::Option(i2)) => i1.cmp(i2),
+            (CandyShared::Array(i1), CandyShared::Array(i2)) => i1.cmp(i2),
+            (CandyShared::Nats(i1), CandyShared::Nats(i2)) => i1.cmp(i2),
+            (CandyShared::Floats(i1), CandyShared::Floats(i2)) => i1
+                .iter()
+                .zip(i2)
+                .map(|(f1, f2)| f1.total_cmp(f2))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| i1.len().cmp(&i2.len())),
+            (CandyShared::Map(i1), CandyShared::Map(i2)) => i1.cmp(i2),
+            (CandyShared::ValueMap(i1), CandyShared::ValueMap(i2)) => i1.cmp(i2),
+            (CandyShared::Set(i1), CandyShared::Set(i2)) => i1.cmp(i2),
+            _ => self.variant_rank().cmp(&other.variant_rank()),
+        }
+    }
+}
+
 impl Display for CandyShared {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -2,6 +2,7 @@ use candid::{CandidType, Encode};
 use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 
+use crate::types::CandyError;
 use crate::value::{CandyShared, ToCandyValue};
 
 /*
@@ -54,13 +55,23 @@ pub trait AddressedChunkArrayTrait {
     /// The `CandyShared` instance representing the desired data chunk, or a `CandyShared::Option(None)` instance if the data chunk is not found.
     fn get_data_chunk(&self, data_zone: u128, data_chunk: u128) -> CandyShared;
 
-    /// Flatten the `AddressedChunkArray` into a byte vector.
+    /// Flatten the `AddressedChunkArray` into a byte vector, trapping on chunks that can not be converted to a blob.
+    #[cfg(feature = "ic")]
     fn flatten(self) -> Vec<u8>;
+
+    /// Flatten the `AddressedChunkArray` into a byte vector.
+    ///
+    /// # Errors
+    ///
+    /// * `CandyError::UnsupportedVariant` if a chunk can not be converted to a blob.
+    fn try_flatten(self) -> Result<Vec<u8>, CandyError>;
 }
 
 pub trait DataZoneTrait {
     fn get_data_zone_size(&self) -> u128;
+    #[cfg(feature = "ic")]
     fn to_bytes_buffer(self) -> Vec<Vec<u8>>;
+    fn try_to_bytes_buffer(self) -> Result<Vec<Vec<u8>>, CandyError>;
     fn from_buffer(bytes_buffer: Vec<Vec<u8>>) -> Self;
 }
 
@@ -149,12 +160,32 @@ impl DataZoneTrait for DataZone {
     /// let buffer = dz.to_bytes_buffer();
     /// assert_eq!(buffer, vec![vec![42]]);
     /// ```
+    #[cfg(feature = "ic")]
     fn to_bytes_buffer(self) -> Vec<Vec<u8>> {
         self.into_iter()
             .map(DataChunk::to_blob)
             .collect::<Vec<Vec<u8>>>()
     }
 
+    /// Convert the DataZone to a vector of byte vectors, failing on the first chunk without a blob representation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_candy::value::{CandyShared, ToCandyValue};
+    /// use ic_candy::workspace::DataZone;
+    /// use crate::ic_candy::workspace::DataZoneTrait;
+    ///
+    /// let dz: DataZone = vec![42_u128.to_candy()];
+    /// assert_eq!(dz.try_to_bytes_buffer(), Ok(vec![vec![42]]));
+    ///
+    /// let dz: DataZone = vec![4.2.to_candy()];
+    /// assert!(dz.try_to_bytes_buffer().is_err());
+    /// ```
+    fn try_to_bytes_buffer(self) -> Result<Vec<Vec<u8>>, CandyError> {
+        self.into_iter().map(DataChunk::try_to_blob).collect()
+    }
+
     /// Convert the DataZone from a vector of byte vectors.
     fn from_buffer(bytes_buffer: Vec<Vec<u8>>) -> Self {
         bytes_buffer.into_iter().map(CandyShared::Bytes).collect()
@@ -175,14 +206,20 @@ impl AddressedChunkArrayTrait for AddressedChunkArray {
         CandyShared::Option(None)
     }
 
+    #[cfg(feature = "ic")]
     fn flatten(self) -> Vec<u8> {
+        self.try_flatten()
+            .unwrap_or_else(|e| ic_cdk::trap(format!("Cannot flatten chunks: {}", e).as_str()))
+    }
+
+    fn try_flatten(self) -> Result<Vec<u8>, CandyError> {
         let mut res: Vec<u8> = Vec::new();
         for item in self {
-            res.append(item.0.to_candy().to_blob().as_mut());
-            res.extend_from_slice(item.1.to_candy().to_blob().as_mut());
-            res.extend_from_slice(item.2.to_blob().as_mut());
+            res.append(item.0.to_candy().try_to_blob()?.as_mut());
+            res.extend_from_slice(item.1.to_candy().try_to_blob()?.as_mut());
+            res.extend_from_slice(item.2.try_to_blob()?.as_mut());
         }
-        res
+        Ok(res)
     }
 }
 
@@ -2,7 +2,7 @@
 
 #[cfg(test)]
 mod collections_test {
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
 
     use candid::Principal;
 
@@ -10,6 +10,7 @@ mod collections_test {
     use ic_candy::value::{CandyShared, ToCandyValue};
 
     #[test]
+    #[allow(non_snake_case)]
     fn hash_map() {
         let mut valueMap: HashMap<CandyShared, CandyShared> = HashMap::new();
         valueMap.insert(1_u128.to_candy(), 2_u128.to_candy());
@@ -202,4 +203,41 @@ mod collections_test {
             assert_eq!(123_u128.to_candy(), *map.get("icp").unwrap());
         }
     }
+
+    #[test]
+    fn deterministic_order() {
+        let keys = ["b", "c", "a"];
+        let forward: HashMap<CandyShared, CandyShared> =
+            keys.iter().map(|k| (k.to_candy(), 1_u8.to_candy())).collect();
+        let backward: HashMap<CandyShared, CandyShared> = keys
+            .iter()
+            .rev()
+            .map(|k| (k.to_candy(), 1_u8.to_candy()))
+            .collect();
+        let (forward, backward) = (forward.to_candy(), backward.to_candy());
+        assert_eq!(forward.to_string(), backward.to_string());
+        assert_eq!(forward.to_tagged_json(), backward.to_tagged_json());
+        assert_eq!(
+            candid::encode_one(&forward).unwrap(),
+            candid::encode_one(&backward).unwrap()
+        );
+
+        let set: BTreeSet<CandyShared> = [2_u8.to_candy(), "a".to_candy(), 1_u8.to_candy()]
+            .into_iter()
+            .collect();
+        let items: Vec<&CandyShared> = set.iter().collect();
+        assert_eq!(
+            items,
+            vec![&1_u8.to_candy(), &2_u8.to_candy(), &"a".to_candy()]
+        );
+
+        // Maps keep their Candid representation, `vec record { key; value }`.
+        let mut map = BTreeMap::<String, CandyShared>::new();
+        map.insert("icp".to_string(), 1_u8.to_candy());
+        let bytes = candid::encode_one(map.clone().to_candy()).unwrap();
+        assert_eq!(
+            candid::decode_one::<CandyShared>(&bytes).unwrap(),
+            map.to_candy()
+        );
+    }
 }
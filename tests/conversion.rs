@@ -1,13 +1,23 @@
 #![allow(unused_imports)]
 #[cfg(test)]
 mod conversion_tests {
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
     use candid::Principal;
     use pretty_assertions::assert_eq;
 
-    use ic_candy::types::PropertyShared;
+    use ic_candy::conversion::{
+        blob_to_int, blob_to_nat, blob_to_nat16, blob_to_nat32, blob_to_nat64, blob_to_principal,
+        blob_to_text,
+    };
+    use ic_candy::json::{
+        BinaryEncoding, JsonOptions, JsonParseOptions, LimitedWriter, NonFiniteFloats,
+    };
+    use ic_candy::types::{CandyError, Property, PropertyShared};
     use ic_candy::value::CandyShared::Bytes;
-    use ic_candy::value::ToCandyValue;
-    use ic_candy::value::{CandyShared, ToBlob};
+    use ic_candy::value::{BlobTarget, CandyShared, CandyValue, NumericConversion, ToBlob};
+    use ic_candy::value::{FromCandyValue, ToCandyValue};
+    use num_bigint::{BigInt, BigUint};
 
     #[test]
     fn conversion_to_nat() {
@@ -95,6 +105,54 @@ mod conversion_tests {
         assert_eq!(i64_candy_neg.to_nat(), None);
     }
 
+    #[test]
+    fn conversion_numeric_modes() {
+        use NumericConversion::{Round, Saturate, Strict, Truncate};
+
+        // Floats: non-integral values only convert when rounding or truncating.
+        let value = 1.6_f64.to_candy();
+        assert_eq!(value.clone().to_nat8(), Some(2));
+        assert_eq!(value.clone().to_nat_strict(), None);
+        assert_eq!(value.clone().to_int_strict(), None);
+        assert_eq!(value.clone().to_nat8_with(Strict), None);
+        assert_eq!(value.clone().to_nat8_with(Round), Some(2));
+        assert_eq!(value.clone().to_nat8_with(Truncate), Some(1));
+        assert_eq!(value.to_int64_with(Saturate), Some(1));
+        assert_eq!(3.0_f64.to_candy().to_nat_strict(), Some(3));
+        assert_eq!((-3.0_f64).to_candy().to_int_strict(), Some(-3));
+        assert_eq!((-0.4_f64).to_candy().to_nat16_with(Round), None);
+        assert_eq!((-0.4_f64).to_candy().to_nat16_with(Truncate), Some(0));
+        assert_eq!((-2.5_f64).to_candy().to_int32_with(Round), Some(-3));
+        assert_eq!((-2.5_f64).to_candy().to_int32_with(Truncate), Some(-2));
+        for mode in [Strict, Round, Truncate, Saturate] {
+            assert_eq!(f64::NAN.to_candy().to_int_with(mode), None);
+            assert_eq!("1".to_candy().to_nat_with(mode), None);
+        }
+
+        // Out of range values only convert when saturating.
+        assert_eq!(300_u16.to_candy().to_nat8_with(Round), None);
+        assert_eq!(300_u16.to_candy().to_nat8_with(Saturate), Some(u8::MAX));
+        assert_eq!((-1_i8).to_candy().to_nat32_with(Strict), None);
+        assert_eq!((-1_i8).to_candy().to_nat32_with(Saturate), Some(0));
+        assert_eq!((-200_i128).to_candy().to_int8_with(Saturate), Some(i8::MIN));
+        assert_eq!(
+            f64::INFINITY.to_candy().to_nat64_with(Saturate),
+            Some(u64::MAX)
+        );
+        assert_eq!(f64::INFINITY.to_candy().to_nat64_with(Truncate), None);
+        assert_eq!(1e40_f64.to_candy().to_int_with(Saturate), Some(i128::MAX));
+        let big = CandyShared::Nat(candid::Nat(BigUint::from(u128::MAX) * 2_u8));
+        assert_eq!(big.clone().to_nat_with(Strict), None);
+        assert_eq!(big.to_nat_with(Saturate), Some(u128::MAX));
+
+        // Integral values convert alike in every mode.
+        for mode in [Strict, Round, Truncate, Saturate] {
+            assert_eq!(42_u64.to_candy().to_nat8_with(mode), Some(42));
+            assert_eq!((-42_i16).to_candy().to_int64_with(mode), Some(-42));
+            assert_eq!(42_i32.to_candy().to_nat_with(mode), Some(42));
+        }
+    }
+
     #[test]
     fn conversion_to_string() {
         // Nat
@@ -228,7 +286,132 @@ mod conversion_tests {
         assert_eq!(floats.to_string(), "[12.35 25.66]".to_string());
     }
 
+    // Expected values are those Motoko's `candySharedToText` and `candySharedToBytes` give.
+    #[test]
+    fn conversion_to_text_and_bytes() {
+        let texts = [
+            ("Hello".to_candy(), "Hello"),
+            (12345_u128.to_candy(), "12345"),
+            ((-12_i16).to_candy(), "-12"),
+            (1.5_f64.to_candy(), "1.5"),
+            (false.to_candy(), "false"),
+            (Bytes(vec![0, 171]), "00ab"),
+            (CandyShared::Blob(vec![255]), "ff"),
+            (Principal::anonymous().to_candy(), "2vxsx-fae"),
+            (CandyShared::Option(None), "null"),
+            (CandyShared::Option(Some(Box::new(7_u8.to_candy()))), "7"),
+            (
+                vec![1_u8.to_candy(), "a".to_candy()].to_candy(),
+                "[{1} {a}]",
+            ),
+            (vec![1_u128, 2].to_candy(), "[1 2]"),
+        ];
+        for (value, text) in texts {
+            assert_eq!(value.clone().to_text(), text);
+            assert_eq!(value.to_string(), text);
+        }
+
+        assert_eq!(Bytes(vec![1, 2]).to_bytes(), Some(vec![1, 2]));
+        assert_eq!(CandyShared::Blob(vec![3]).to_bytes(), Some(vec![3]));
+        assert_eq!(7_u8.to_candy().to_bytes(), Some(vec![7]));
+        assert_eq!(65_536_u32.to_candy().to_bytes(), Some(vec![0, 1, 0, 0]));
+        assert_eq!(256_u128.to_candy().to_bytes(), Some(vec![1, 0]));
+        assert_eq!("A".to_candy().to_bytes(), Some(vec![0, 0, 0, 65]));
+        assert_eq!(Principal::anonymous().to_candy().to_bytes(), Some(vec![4]));
+        for value in [
+            1.5_f64.to_candy(),
+            true.to_candy(),
+            CandyShared::Option(None),
+            vec![1_u8.to_candy()].to_candy(),
+        ] {
+            assert_eq!(value.to_bytes(), None);
+        }
+    }
+
+    #[test]
+    fn conversion_to_collections() {
+        // Typed arrays widen into arrays of their items.
+        assert_eq!(
+            vec![1_u128, 2].to_candy().to_value_array(),
+            Some(vec![1_u128.to_candy(), 2_u128.to_candy()])
+        );
+        assert_eq!(
+            vec![-1_i128].to_candy().to_value_array(),
+            Some(vec![(-1_i128).to_candy()])
+        );
+        assert_eq!(
+            vec![0.5_f64].to_candy().to_value_array(),
+            Some(vec![0.5_f64.to_candy()])
+        );
+        assert_eq!(
+            set_of(&[2, 1]).to_value_array(),
+            Some(vec![1_u8.to_candy(), 2_u8.to_candy()])
+        );
+        assert_eq!(Bytes(vec![1]).to_value_array(), None);
+
+        // Numbers
+        let mixed = vec![1_u8.to_candy(), 2_i64.to_candy(), 3.4_f64.to_candy()].to_candy();
+        assert_eq!(mixed.clone().to_nats(), Some(vec![1, 2, 3]));
+        assert_eq!(mixed.clone().to_ints(), Some(vec![1, 2, 3]));
+        assert_eq!(mixed.to_floats(), Some(vec![1.0, 2.0, 3.4]));
+        assert_eq!(Bytes(vec![1, 2]).to_nats(), Some(vec![1, 2]));
+        assert_eq!(set_of(&[3]).to_ints(), Some(vec![3]));
+        assert_eq!(vec![-1_i128].to_candy().to_nats(), None);
+        assert_eq!(vec![-1_i128].to_candy().to_ints(), Some(vec![-1]));
+        assert_eq!(
+            vec![1_u8.to_candy(), "2".to_candy()].to_candy().to_floats(),
+            None
+        );
+        assert_eq!(1_u8.to_candy().to_nats(), None);
+
+        // Classes, maps and sets
+        let props = vec![
+            PropertyShared {
+                name: "a".to_string(),
+                value: 1_u8.to_candy(),
+                immutable: true,
+            },
+            PropertyShared {
+                name: "b".to_string(),
+                value: 2_u8.to_candy(),
+                immutable: false,
+            },
+        ];
+        assert_eq!(props.clone().to_candy().to_class(), Some(props.clone()));
+        assert_eq!(
+            BTreeMap::<String, CandyShared>::new().to_candy().to_class(),
+            None
+        );
+
+        let expected = BTreeMap::from([
+            ("a".to_string(), 1_u8.to_candy()),
+            ("b".to_string(), 2_u8.to_candy()),
+        ]);
+        assert_eq!(props.to_candy().to_map(), Some(expected.clone()));
+        assert_eq!(expected.clone().to_candy().to_map(), Some(expected.clone()));
+        let mut value_map = HashMap::new();
+        value_map.insert("a".to_candy(), 1_u8.to_candy());
+        value_map.insert("b".to_candy(), 2_u8.to_candy());
+        assert_eq!(value_map.to_candy().to_map(), Some(expected));
+        let mut value_map = HashMap::new();
+        value_map.insert(1_u8.to_candy(), 1_u8.to_candy());
+        assert_eq!(value_map.to_candy().to_map(), None);
+
+        let CandyShared::Set(set) = set_of(&[1, 2]) else {
+            panic!("expected a set");
+        };
+        assert_eq!(set_of(&[1, 2]).to_set(), Some(set.clone()));
+        assert_eq!(
+            vec![2_u8.to_candy(), 1_u8.to_candy(), 2_u8.to_candy()]
+                .to_candy()
+                .to_set(),
+            Some(set)
+        );
+        assert_eq!(1_u8.to_candy().to_set(), None);
+    }
+
     #[test]
+    #[cfg(feature = "ic")]
     fn conversion_to_blob() {
         // Nat
         let num = 255_u128;
@@ -278,6 +461,89 @@ mod conversion_tests {
         assert_eq!(result, vec![4]);
     }
 
+    #[test]
+    fn conversion_try_to_blob() {
+        assert_eq!(255_u128.to_candy().try_to_blob(), Ok(vec![255]));
+        assert_eq!(2566_u16.to_candy().try_to_blob(), Ok(vec![10, 6]));
+        assert_eq!((-123_i128).to_candy().try_to_blob(), Ok(vec![1, 123]));
+        assert_eq!(Bytes(vec![1, 2, 3]).try_to_blob(), Ok(vec![1, 2, 3]));
+        assert_eq!(
+            CandyShared::from(Principal::anonymous()).try_to_blob(),
+            Ok(vec![4])
+        );
+
+        //Unsupported variants
+        assert_eq!(
+            12.35.to_candy().try_to_blob(),
+            Err(CandyError::UnsupportedVariant("Float".to_string()))
+        );
+        assert_eq!(
+            vec![1.to_candy()].to_candy().try_to_blob(),
+            Err(CandyError::UnsupportedVariant("Array".to_string()))
+        );
+        assert_eq!(
+            CandyShared::from(None).try_to_blob(),
+            Err(CandyError::UnsupportedVariant("Option".to_string()))
+        );
+    }
+
+    #[test]
+    fn conversion_from_blob() {
+        // Round trips
+        let values = vec![
+            (0_u128.to_candy(), BlobTarget::Nat),
+            (255_u128.to_candy(), BlobTarget::Nat),
+            (u128::MAX.to_candy(), BlobTarget::Nat),
+            (0_i128.to_candy(), BlobTarget::Int),
+            ((-123_i128).to_candy(), BlobTarget::Int),
+            (i128::MIN.to_candy(), BlobTarget::Int),
+            (255_u8.to_candy(), BlobTarget::Nat8),
+            (2566_u16.to_candy(), BlobTarget::Nat16),
+            (255_u32.to_candy(), BlobTarget::Nat32),
+            (300_000_u64.to_candy(), BlobTarget::Nat64),
+            ("Hello, world! ✓".to_candy(), BlobTarget::Text),
+            (vec![1_u8, 2_u8].to_candy(), BlobTarget::Blob),
+            (Bytes(vec![1_u8, 2_u8]), BlobTarget::Bytes),
+            (Principal::anonymous().to_candy(), BlobTarget::Principal),
+            (
+                Principal::management_canister().to_candy(),
+                BlobTarget::Principal,
+            ),
+        ];
+        for (value, target) in values {
+            let blob = value.clone().try_to_blob().unwrap();
+            assert_eq!(CandyShared::from_blob(&blob, target), Ok(value));
+        }
+
+        // Typed decoders
+        assert_eq!(blob_to_nat(&[]), Ok(BigUint::from(0_u32)));
+        assert_eq!(blob_to_nat(&[1, 0]), Ok(BigUint::from(256_u32)));
+        assert_eq!(blob_to_int(&[0, 1, 0]), Ok(BigInt::from(128)));
+        assert_eq!(blob_to_nat16(&[10, 6]), Ok(2566));
+        assert_eq!(blob_to_nat32(&[0, 0, 0, 255]), Ok(255));
+        assert_eq!(blob_to_nat64(&[0, 0, 0, 0, 0, 4, 147, 224]), Ok(300_000));
+        assert_eq!(
+            blob_to_text(&[0, 0, 0, 72, 0, 0, 0, 105]),
+            Ok("Hi".to_string())
+        );
+        assert_eq!(blob_to_principal(&[4]), Ok(Principal::anonymous()));
+
+        // Malformed input
+        assert!(matches!(
+            blob_to_text(&[0, 0, 72]),
+            Err(CandyError::InvalidEncoding(_))
+        ));
+        assert!(blob_to_text(&[0, 0, 0xd8, 0]).is_err());
+        assert!(blob_to_int(&[]).is_err());
+        assert!(blob_to_int(&[0]).is_err());
+        assert!(blob_to_int(&[2, 1]).is_err());
+        assert!(blob_to_int(&[0, 128]).is_err());
+        assert!(blob_to_nat16(&[1]).is_err());
+        assert!(blob_to_nat32(&[1, 2, 3, 4, 5]).is_err());
+        assert!(blob_to_nat64(&[]).is_err());
+        assert!(blob_to_principal(&[0; 30]).is_err());
+    }
+
     #[test]
     fn conversion_to_json() {
         // Nat
@@ -371,5 +637,667 @@ mod conversion_tests {
         //Int8
         let num = 123_i8.to_candy();
         assert_eq!(num.to_json(), "123");
+
+        //Map
+        let map: HashMap<String, CandyShared> = [
+            ("b".to_string(), 2_u8.to_candy()),
+            ("a".to_string(), "x".to_candy()),
+            ("c".to_string(), vec![1_u8.to_candy()].to_candy()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(map.to_candy().to_json(), "{\"a\":\"x\",\"b\":2,\"c\":[1]}");
+
+        //ValueMap with text keys
+        let value_map: HashMap<CandyShared, CandyShared> = [
+            ("z".to_candy(), 1_u8.to_candy()),
+            ("y".to_candy(), 2_u8.to_candy()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(value_map.to_candy().to_json(), "{\"y\":2,\"z\":1}");
+
+        //ValueMap with other keys
+        let value_map: HashMap<CandyShared, CandyShared> = [
+            (2_u8.to_candy(), "b".to_candy()),
+            (1_u8.to_candy(), "a".to_candy()),
+            ("c".to_candy(), "c".to_candy()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            value_map.to_candy().to_json(),
+            "[[1,\"a\"],[2,\"b\"],[\"c\",\"c\"]]"
+        );
+
+        //Set
+        let set: HashSet<CandyShared> = [3_u8.to_candy(), 1_u8.to_candy(), 2_u8.to_candy()]
+            .into_iter()
+            .collect();
+        assert_eq!(set.to_candy().to_json(), "[1,2,3]");
+
+        //Nested
+        let nested = vec![PropertyShared {
+            name: "set".to_string(),
+            value: HashSet::<CandyShared>::new().to_candy(),
+            immutable: false,
+        }]
+        .to_candy();
+        assert_eq!(nested.to_json(), "{\"set\":[]}");
+    }
+
+    #[test]
+    fn conversion_from_json() {
+        let json = r#"{"name": "candy", "id": 18446744073709551616, "delta": -3,
+            "ratio": 1.5e2, "on": true, "tags": ["a", "b\u00e9"], "none": null, "nested": {}}"#;
+        assert_eq!(
+            CandyShared::from_json(json),
+            Ok(vec![
+                PropertyShared {
+                    name: "name".to_string(),
+                    value: "candy".to_candy(),
+                    immutable: false
+                },
+                PropertyShared {
+                    name: "id".to_string(),
+                    value: (u64::MAX as u128 + 1).to_candy(),
+                    immutable: false
+                },
+                PropertyShared {
+                    name: "delta".to_string(),
+                    value: (-3_i128).to_candy(),
+                    immutable: false
+                },
+                PropertyShared {
+                    name: "ratio".to_string(),
+                    value: 150.0.to_candy(),
+                    immutable: false
+                },
+                PropertyShared {
+                    name: "on".to_string(),
+                    value: true.to_candy(),
+                    immutable: false
+                },
+                PropertyShared {
+                    name: "tags".to_string(),
+                    value: vec!["a".to_candy(), "bé".to_candy()].to_candy(),
+                    immutable: false
+                },
+                PropertyShared {
+                    name: "none".to_string(),
+                    value: CandyShared::Option(None),
+                    immutable: false
+                },
+                PropertyShared {
+                    name: "nested".to_string(),
+                    value: Vec::<PropertyShared>::new().to_candy(),
+                    immutable: false
+                },
+            ]
+            .to_candy())
+        );
+
+        // Round trip through to_json
+        let value = CandyShared::from_json(r#"{"a":[1,-2,3.5],"b":"\ud83c\udf6c"}"#).unwrap();
+        assert_eq!(value.clone().to_json(), "{\"a\":[1,-2,3.5],\"b\":\"🍬\"}");
+        assert_eq!(CandyShared::from_json(&value.clone().to_json()), Ok(value));
+
+        // Typed arrays
+        let options = JsonParseOptions {
+            typed_arrays: true,
+            hex_as_blob: false,
+        };
+        assert_eq!(
+            CandyShared::from_json_with("[1, 2]", &options),
+            Ok(vec![1_u128, 2_u128].to_candy())
+        );
+        assert_eq!(
+            CandyShared::from_json_with("[1, -2]", &options),
+            Ok(vec![1_i128, -2_i128].to_candy())
+        );
+        assert_eq!(
+            CandyShared::from_json_with("[1.5, -2.0, 0.5]", &options),
+            Ok(vec![1.5, -2.0, 0.5].to_candy())
+        );
+        // Mixed integers and floats are not converted, large integers would lose precision.
+        assert_eq!(
+            CandyShared::from_json_with("[18446744073709551617, -2, 0.5]", &options),
+            Ok(vec![
+                candid::Nat::from(18446744073709551617_u128).to_candy(),
+                (-2_i128).to_candy(),
+                0.5.to_candy()
+            ]
+            .to_candy())
+        );
+        assert_eq!(
+            CandyShared::from_json_with("[1, \"a\"]", &options),
+            Ok(vec![1_u128.to_candy(), "a".to_candy()].to_candy())
+        );
+        assert_eq!(
+            CandyShared::from_json_with("[]", &options),
+            Ok(Vec::<CandyShared>::new().to_candy())
+        );
+
+        // Hex blobs
+        let options = JsonParseOptions {
+            typed_arrays: false,
+            hex_as_blob: true,
+        };
+        assert_eq!(
+            CandyShared::from_json_with("[\"010203\", \"0f0\", \"\"]", &options),
+            Ok(vec![
+                vec![1_u8, 2_u8, 3_u8].to_candy(),
+                "0f0".to_candy(),
+                "".to_candy()
+            ]
+            .to_candy())
+        );
+
+        // Malformed input
+        for json in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "01",
+            "1.",
+            "-",
+            "tru",
+            "\"abc",
+            "\"\\x\"",
+            "1 2",
+        ] {
+            assert!(
+                matches!(
+                    CandyShared::from_json(json),
+                    Err(CandyError::InvalidEncoding(_))
+                ),
+                "{}",
+                json
+            );
+        }
+        assert!(CandyShared::from_json(&"[".repeat(200)).is_err());
+    }
+
+    #[test]
+    fn conversion_tagged_json() {
+        let value_map: HashMap<CandyShared, CandyShared> = [
+            (1_u8.to_candy(), "a".to_candy()),
+            ("b".to_candy(), vec![2.5, f64::NAN].to_candy()),
+        ]
+        .into_iter()
+        .collect();
+        let map: HashMap<String, CandyShared> = [
+            ("x".to_string(), CandyShared::Option(None)),
+            ("y".to_string(), value_map.to_candy()),
+        ]
+        .into_iter()
+        .collect();
+        let set: HashSet<CandyShared> = [1_u8.to_candy(), 1_u16.to_candy()].into_iter().collect();
+        let value = vec![
+            PropertyShared {
+                name: "ints".to_string(),
+                value: vec![
+                    (-1_i128).to_candy(),
+                    (-2_i8).to_candy(),
+                    (-3_i16).to_candy(),
+                    (-4_i32).to_candy(),
+                    i64::MIN.to_candy(),
+                    vec![i128::MIN, 0].to_candy(),
+                ]
+                .to_candy(),
+                immutable: true,
+            },
+            PropertyShared {
+                name: "nats".to_string(),
+                value: vec![
+                    u128::MAX.to_candy(),
+                    1_u8.to_candy(),
+                    2_u16.to_candy(),
+                    3_u32.to_candy(),
+                    u64::MAX.to_candy(),
+                    vec![1_u128, 2_u128].to_candy(),
+                ]
+                .to_candy(),
+                immutable: false,
+            },
+            PropertyShared {
+                name: "floats".to_string(),
+                value: vec![
+                    0.1.to_candy(),
+                    (-0.0).to_candy(),
+                    1e300.to_candy(),
+                    f64::NEG_INFINITY.to_candy(),
+                    vec![f64::INFINITY, f64::MIN_POSITIVE].to_candy(),
+                ]
+                .to_candy(),
+                immutable: false,
+            },
+            PropertyShared {
+                name: "other".to_string(),
+                value: vec![
+                    "te\"xt".to_candy(),
+                    false.to_candy(),
+                    vec![0_u8, 255_u8].to_candy(),
+                    Bytes(vec![1_u8]),
+                    Principal::anonymous().to_candy(),
+                    Some(Box::new(CandyShared::Option(None))).to_candy(),
+                    map.to_candy(),
+                    set.to_candy(),
+                ]
+                .to_candy(),
+                immutable: false,
+            },
+        ]
+        .to_candy();
+
+        let json = value.to_tagged_json();
+        let parsed = CandyShared::from_tagged_json(&json).unwrap();
+        assert_eq!(parsed.to_tagged_json(), json);
+        // NaN is never equal to itself, compare the rest structurally
+        if let (CandyShared::Class(a), CandyShared::Class(b)) = (&value, &parsed) {
+            assert_eq!(a[..3], b[..3]);
+        }
+        if let CandyShared::Class(props) = &parsed {
+            if let CandyShared::Array(floats) = &props[2].value {
+                assert!(floats[1].clone().to_float().unwrap().is_sign_negative());
+            }
+        }
+
+        // Variant information is preserved
+        assert_eq!(123_u8.to_candy().to_tagged_json(), r#"{"Nat8":123}"#);
+        assert_eq!(123_u64.to_candy().to_tagged_json(), r#"{"Nat64":"123"}"#);
+        assert_eq!(vec![1_u8].to_candy().to_tagged_json(), r#"{"Blob":"01"}"#);
+        assert_eq!(Bytes(vec![1_u8]).to_tagged_json(), r#"{"Bytes":"01"}"#);
+        assert_eq!(
+            set_of(&[2_u8, 1_u8]).to_tagged_json(),
+            r#"{"Set":[{"Nat8":1},{"Nat8":2}]}"#
+        );
+
+        // Malformed input
+        for json in [
+            r#"{"Nat8":256}"#,
+            r#"{"Nat":"-1"}"#,
+            r#"{"Bool":"true"}"#,
+            r#"{"Blob":"0g"}"#,
+            r#"{"Float":"nan"}"#,
+            r#"{"Unknown":1}"#,
+            r#"{"Nat8":1,"Nat16":1}"#,
+            r#"{"Class":[{"name":"a","value":{"Nat8":1}}]}"#,
+            r#"{"ValueMap":[[{"Nat8":1}]]}"#,
+            r#"{"Principal":"not a principal"}"#,
+            "1",
+        ] {
+            assert!(CandyShared::from_tagged_json(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn conversion_to_json_with() {
+        // Defaults produce the same output as to_json for plain values
+        let value = vec![
+            PropertyShared {
+                name: "n".to_string(),
+                value: 123_u128.to_candy(),
+                immutable: false,
+            },
+            PropertyShared {
+                name: "b".to_string(),
+                value: true.to_candy(),
+                immutable: false,
+            },
+            PropertyShared {
+                name: "blob".to_string(),
+                value: vec![1_u8, 2_u8, 3_u8].to_candy(),
+                immutable: false,
+            },
+        ]
+        .to_candy();
+        assert_eq!(
+            value.to_json_with(&JsonOptions::new()),
+            value.clone().to_json()
+        );
+
+        // Pretty printing
+        assert_eq!(
+            value.to_json_with(&JsonOptions::new().indent(2).native_bools(true)),
+            "{\n  \"n\": 123,\n  \"b\": true,\n  \"blob\": \"010203\"\n}"
+        );
+        let nested = vec![
+            vec![1_u8.to_candy()].to_candy(),
+            Vec::<CandyShared>::new().to_candy(),
+        ]
+        .to_candy();
+        assert_eq!(
+            nested.to_json_with(&JsonOptions::new().indent(1)),
+            "[\n [\n  1\n ],\n []\n]"
+        );
+
+        // Big numbers
+        let options = JsonOptions::new().big_numbers_as_strings(1 << 53);
+        assert_eq!(
+            (1_u128 << 53).to_candy().to_json_with(&options),
+            "9007199254740992"
+        );
+        assert_eq!(
+            ((1_u128 << 53) + 1).to_candy().to_json_with(&options),
+            "\"9007199254740993\""
+        );
+        assert_eq!(
+            i64::MIN.to_candy().to_json_with(&options),
+            "\"-9223372036854775808\""
+        );
+        assert_eq!(
+            vec![1_u128, u128::MAX].to_candy().to_json_with(&options),
+            "[1,\"340282366920938463463374607431768211455\"]"
+        );
+        // The threshold applies to integers of every width.
+        let options = JsonOptions::new().big_numbers_as_strings(1000);
+        let integers = vec![
+            4_000_000_000_u32.to_candy(),
+            1000_u16.to_candy(),
+            1001_u16.to_candy(),
+            (-1001_i16).to_candy(),
+            i32::MIN.to_candy(),
+            255_u8.to_candy(),
+            i8::MIN.to_candy(),
+        ]
+        .to_candy();
+        assert_eq!(
+            integers.to_json_with(&options),
+            "[\"4000000000\",1000,\"1001\",\"-1001\",\"-2147483648\",255,-128]"
+        );
+        assert_eq!(
+            vec![1_u128, 1_234_u128]
+                .to_candy()
+                .to_json_with(&JsonOptions::new()),
+            "[1,1234]"
+        );
+
+        // Binary
+        let options = JsonOptions::new().binary_encoding(BinaryEncoding::Base64);
+        assert_eq!(Bytes(vec![0xff, 0xee]).to_json_with(&options), "\"/+4=\"");
+
+        // Non-finite floats
+        let floats = vec![f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1.5].to_candy();
+        assert_eq!(
+            floats.to_json_with(&JsonOptions::new()),
+            "[null,null,null,1.5]"
+        );
+        assert_eq!(
+            floats.to_json_with(&JsonOptions::new().non_finite_floats(NonFiniteFloats::String)),
+            "[\"NaN\",\"Infinity\",\"-Infinity\",1.5]"
+        );
+
+        // Maps are sorted in every mode
+        let map: HashMap<String, CandyShared> = [
+            ("b".to_string(), 2_u8.to_candy()),
+            ("a".to_string(), 1_u8.to_candy()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            map.to_candy().to_json_with(&JsonOptions::new().indent(2)),
+            "{\n  \"a\": 1,\n  \"b\": 2\n}"
+        );
+        let value_map: HashMap<CandyShared, CandyShared> =
+            [(1_u8.to_candy(), 2_u8.to_candy())].into_iter().collect();
+        assert_eq!(
+            value_map.to_candy().to_json_with(&JsonOptions::new()),
+            "[[1,2]]"
+        );
+    }
+
+    #[test]
+    fn conversion_write_json() {
+        let text = "quote \" backslash \\ newline \n tab \t bell \u{7} é 🍬";
+        let value = vec![
+            PropertyShared {
+                name: "text".to_string(),
+                value: text.to_candy(),
+                immutable: false,
+            },
+            PropertyShared {
+                name: "items".to_string(),
+                value: (0..1000_u32)
+                    .map(|i| i.to_candy())
+                    .collect::<Vec<CandyShared>>()
+                    .to_candy(),
+                immutable: false,
+            },
+        ]
+        .to_candy();
+
+        let mut out = String::new();
+        value.write_json(&mut out).unwrap();
+        assert_eq!(out, value.to_json_with(&JsonOptions::new()));
+        assert_eq!(
+            text.to_candy().to_json_with(&JsonOptions::new()),
+            serde_json::to_string(text).unwrap()
+        );
+
+        // Byte limit
+        let mut out = String::new();
+        let mut limited = LimitedWriter::new(&mut out, 100);
+        assert_eq!(value.write_json(&mut limited), Err(std::fmt::Error));
+        assert!(limited.limit_reached());
+        assert_eq!(out.len(), 100);
+        assert!(value.to_json_with(&JsonOptions::new()).starts_with(&out));
+
+        let mut out = String::new();
+        let mut limited = LimitedWriter::new(&mut out, 2);
+        assert!("é".to_candy().write_json(&mut limited).is_err());
+        assert!(limited.limit_reached());
+        assert_eq!(out, "\"");
+
+        let mut out = String::new();
+        let mut limited = LimitedWriter::new(&mut out, 3);
+        assert!(1_u8.to_candy().write_json(&mut limited).is_ok());
+        assert!(!limited.limit_reached());
+        assert_eq!(out, "1");
+
+        // A failing writer is not mistaken for the limit.
+        struct Failing;
+        impl std::fmt::Write for Failing {
+            fn write_str(&mut self, _: &str) -> std::fmt::Result {
+                Err(std::fmt::Error)
+            }
+        }
+        let mut failing = Failing;
+        let mut limited = LimitedWriter::new(&mut failing, 100);
+        assert!(value.write_json(&mut limited).is_err());
+        assert!(!limited.limit_reached());
+    }
+
+    #[test]
+    fn conversion_share_unshare() {
+        let mut map = HashMap::<String, CandyShared>::new();
+        map.insert("a".to_string(), 1_u8.to_candy());
+        let mut value_map = HashMap::<CandyShared, CandyShared>::new();
+        value_map.insert(1_u8.to_candy(), vec![2_u8.to_candy()].to_candy());
+        let value = vec![
+            PropertyShared {
+                name: "array".to_string(),
+                value: vec![
+                    1_u8.to_candy(),
+                    CandyShared::Option(Some(Box::new("x".to_candy()))),
+                ]
+                .to_candy(),
+                immutable: true,
+            },
+            PropertyShared {
+                name: "map".to_string(),
+                value: map.to_candy(),
+                immutable: false,
+            },
+            PropertyShared {
+                name: "value_map".to_string(),
+                value: value_map.to_candy(),
+                immutable: false,
+            },
+            PropertyShared {
+                name: "set".to_string(),
+                value: set_of(&[1, 2]),
+                immutable: false,
+            },
+            PropertyShared {
+                name: "scalars".to_string(),
+                value: vec![
+                    (-1_i128).to_candy(),
+                    1_u128.to_candy(),
+                    1.5_f64.to_candy(),
+                    true.to_candy(),
+                    Bytes(vec![1, 2]),
+                    Principal::anonymous().to_candy(),
+                    vec![1_u128, 2].to_candy(),
+                    vec![1.5_f64].to_candy(),
+                ]
+                .to_candy(),
+                immutable: false,
+            },
+        ]
+        .to_candy();
+
+        let mut mutable = value.clone().unshare();
+        assert_eq!(CandyShared::from(mutable.clone()), value);
+
+        let CandyValue::Class(props) = &mut mutable else {
+            panic!("expected a class");
+        };
+        let CandyValue::Array(items) = &mut props[0].value else {
+            panic!("expected an array");
+        };
+        items.push(CandyValue::Nat8(2));
+        props.push(Property {
+            name: "added".to_string(),
+            value: CandyValue::Bool(false),
+            immutable: false,
+        });
+        let shared = mutable.share();
+        assert_eq!(
+            shared.get(&"array[2]".parse().unwrap()),
+            Some(&2_u8.to_candy())
+        );
+        assert_eq!(
+            shared.get(&"added".parse().unwrap()),
+            Some(&false.to_candy())
+        );
+    }
+
+    #[test]
+    fn conversion_from_candy_value() {
+        assert_eq!(u16::from_candy(300_u128.to_candy()), Ok(300));
+        assert_eq!(
+            u8::from_candy(300_u128.to_candy()),
+            Err(CandyError::Overflow)
+        );
+        assert_eq!(BigInt::from_candy((-5_i8).to_candy()), Ok(BigInt::from(-5)));
+        // Floats are never rounded into integers.
+        assert_eq!(u64::from_candy(2.0_f64.to_candy()), Ok(2));
+        assert_eq!(
+            u64::from_candy(1.6_f64.to_candy()),
+            Err(CandyError::Overflow)
+        );
+        assert_eq!(u64::try_from(2.5_f64.to_candy()), Err(CandyError::Overflow));
+        assert_eq!(
+            i32::from_candy((-1.5_f64).to_candy()),
+            Err(CandyError::Overflow)
+        );
+        assert_eq!(
+            Vec::<u8>::from_candy(vec![1.0, 1.6].to_candy()),
+            Err(CandyError::Overflow)
+        );
+        assert_eq!(
+            String::from_candy(1_u8.to_candy()),
+            Err(CandyError::UnsupportedVariant("Nat8".to_string()))
+        );
+
+        // Collections
+        assert_eq!(Vec::<u8>::from_candy(Bytes(vec![1, 2])), Ok(vec![1, 2]));
+        assert_eq!(
+            Vec::<u32>::from_candy(vec![1_u128, 2].to_candy()),
+            Ok(vec![1, 2])
+        );
+        assert_eq!(
+            Vec::<i64>::from_candy(vec![1_u8.to_candy(), (-1_i8).to_candy()].to_candy()),
+            Ok(vec![1, -1])
+        );
+        assert_eq!(
+            BTreeSet::<u8>::from_candy(set_of(&[2, 1])),
+            Ok(BTreeSet::from([1, 2]))
+        );
+        assert_eq!(
+            Option::<Vec<String>>::from_candy(CandyShared::Option(Some(Box::new(
+                vec!["a".to_candy()].to_candy()
+            )))),
+            Ok(Some(vec!["a".to_string()]))
+        );
+        assert_eq!(
+            Option::<u8>::from_candy(CandyShared::Option(None)),
+            Ok(None)
+        );
+        assert_eq!(
+            Option::<u8>::from_candy(1_u8.to_candy()),
+            Err(CandyError::UnsupportedVariant("Nat8".to_string()))
+        );
+
+        let mut map = HashMap::<String, CandyShared>::new();
+        map.insert("a".to_string(), 1_u8.to_candy());
+        assert_eq!(
+            HashMap::<String, u64>::from_candy(map.to_candy()),
+            Ok(HashMap::from([("a".to_string(), 1)]))
+        );
+        let mut value_map = HashMap::<CandyShared, CandyShared>::new();
+        value_map.insert(1_u8.to_candy(), Bytes(vec![2]));
+        assert_eq!(
+            BTreeMap::<u8, Vec<u8>>::from_candy(value_map.to_candy()),
+            Ok(BTreeMap::from([(1, vec![2])]))
+        );
+        let class = vec![PropertyShared {
+            name: "a".to_string(),
+            value: true.to_candy(),
+            immutable: false,
+        }]
+        .to_candy();
+        assert_eq!(
+            BTreeMap::<String, bool>::from_candy(class),
+            Ok(BTreeMap::from([("a".to_string(), true)]))
+        );
+
+        // Tuples, from arrays and from classes like Candid tuples
+        assert_eq!(
+            <(u8, String)>::from_candy(vec![1_u8.to_candy(), "a".to_candy()].to_candy()),
+            Ok((1, "a".to_string()))
+        );
+        let pair = vec![
+            PropertyShared {
+                name: "0".to_string(),
+                value: (-1_i8).to_candy(),
+                immutable: false,
+            },
+            PropertyShared {
+                name: "1".to_string(),
+                value: 0.5_f64.to_candy(),
+                immutable: false,
+            },
+        ]
+        .to_candy();
+        assert_eq!(<(i8, f64)>::from_candy(pair), Ok((-1, 0.5)));
+        assert_eq!(
+            <(u8, u8, u8)>::from_candy(vec![1_u128, 2].to_candy()),
+            Err(CandyError::UnsupportedVariant("Nats".to_string()))
+        );
+        assert_eq!(
+            <(u8, u8)>::from_candy(vec![1_u8.to_candy(), "a".to_candy()].to_candy()),
+            Err(CandyError::UnsupportedVariant("Text".to_string()))
+        );
+    }
+
+    fn set_of(items: &[u8]) -> CandyShared {
+        items
+            .iter()
+            .map(|i| i.to_candy())
+            .collect::<HashSet<CandyShared>>()
+            .to_candy()
     }
 }
@@ -0,0 +1,126 @@
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod diff_tests {
+    use std::collections::{HashMap, HashSet};
+
+    use pretty_assertions::assert_eq;
+
+    use ic_candy::diff::{diff, Change, ChangeKind};
+    use ic_candy::path::{CandyPath, PathSegment};
+    use ic_candy::types::PropertyShared;
+    use ic_candy::value::{CandyShared, ToCandyValue};
+
+    fn prop(name: &str, value: CandyShared, immutable: bool) -> PropertyShared {
+        PropertyShared {
+            name: name.to_string(),
+            value,
+            immutable,
+        }
+    }
+
+    #[test]
+    fn diff_equal_values() {
+        let value = vec![prop("a", vec![1_u8.to_candy()].to_candy(), true)].to_candy();
+        assert_eq!(diff(&value, &value.clone()), vec![]);
+    }
+
+    #[test]
+    fn diff_nested_values() {
+        let mut old_map = HashMap::<String, CandyShared>::new();
+        old_map.insert("kept".to_string(), 1_u8.to_candy());
+        old_map.insert("gone".to_string(), 2_u8.to_candy());
+        let mut new_map = old_map.clone();
+        new_map.remove("gone");
+        new_map.insert("kept".to_string(), 3_u8.to_candy());
+        new_map.insert("new".to_string(), 4_u8.to_candy());
+
+        let mut old_value_map = HashMap::<CandyShared, CandyShared>::new();
+        old_value_map.insert(1_u8.to_candy(), "one".to_candy());
+        let mut new_value_map = HashMap::<CandyShared, CandyShared>::new();
+        new_value_map.insert(1_u8.to_candy(), "uno".to_candy());
+        new_value_map.insert("two".to_candy(), "dos".to_candy());
+
+        let old_set: HashSet<CandyShared> =
+            [1_u8.to_candy(), 2_u8.to_candy()].into_iter().collect();
+        let new_set: HashSet<CandyShared> =
+            [2_u8.to_candy(), 3_u8.to_candy()].into_iter().collect();
+
+        let old = vec![
+            prop("name", "candy".to_candy(), false),
+            prop("removed", true.to_candy(), false),
+            prop(
+                "list",
+                vec![1_u8.to_candy(), 2_u8.to_candy(), 3_u8.to_candy()].to_candy(),
+                false,
+            ),
+            prop("map", old_map.to_candy(), false),
+            prop("value_map", old_value_map.to_candy(), false),
+            prop("set", old_set.to_candy(), false),
+            prop("type", 1_u8.to_candy(), false),
+            prop(
+                "option",
+                Some(Box::new(vec![prop("x", 1_u8.to_candy(), false)].to_candy())).to_candy(),
+                false,
+            ),
+        ]
+        .to_candy();
+        let new = vec![
+            prop("name", "candy".to_candy(), true),
+            prop(
+                "list",
+                vec![1_u8.to_candy(), 5_u8.to_candy()].to_candy(),
+                false,
+            ),
+            prop("map", new_map.to_candy(), false),
+            prop("value_map", new_value_map.to_candy(), false),
+            prop("set", new_set.to_candy(), false),
+            prop("type", 1_u16.to_candy(), false),
+            prop(
+                "option",
+                Some(Box::new(vec![prop("x", 2_u8.to_candy(), false)].to_candy())).to_candy(),
+                false,
+            ),
+            prop("added", "new".to_candy(), false),
+        ]
+        .to_candy();
+
+        let changes: Vec<String> = diff(&old, &new).iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            changes,
+            vec![
+                "! name: immutable false -> true",
+                "- removed: true",
+                "~ list[1]: 2 -> 5",
+                "- list[2]: 3",
+                "- map.gone: 2",
+                "~ map.kept: 1 -> 3",
+                "+ map.new: 4",
+                "~ value_map[{\"Nat8\":1}]: one -> uno",
+                "+ value_map.two: dos",
+                "- set[{\"Nat8\":1}]: 1",
+                "+ set[{\"Nat8\":3}]: 3",
+                "~ type: 1 -> 1",
+                "~ option.x: 1 -> 2",
+                "+ added: new",
+            ]
+        );
+
+        // Every reported path can be resolved
+        for change in diff(&old, &new) {
+            match change.kind {
+                ChangeKind::Added(value) => assert_eq!(new.get(&change.path), Some(&value)),
+                ChangeKind::Removed(value) => assert_eq!(old.get(&change.path), Some(&value)),
+                ChangeKind::Modified { old: o, new: n } => {
+                    assert_eq!(old.get(&change.path), Some(&o));
+                    assert_eq!(new.get(&change.path), Some(&n));
+                }
+                ChangeKind::ImmutableChanged { .. } => {
+                    assert!(new.get(&change.path).is_some())
+                }
+            }
+            let path: CandyPath = change.path.to_string().parse().unwrap();
+            assert_eq!(path, change.path);
+        }
+    }
+}
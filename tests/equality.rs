@@ -0,0 +1,269 @@
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod equality_tests {
+    use std::cmp::Ordering;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+    use std::hash::{Hash, Hasher};
+
+    use candid::Principal;
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+
+    use ic_candy::types::PropertyShared;
+    use ic_candy::value::{CandyShared, ToCandyValue};
+
+    fn hash(value: &CandyShared) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn float() -> impl Strategy<Value = f64> {
+        prop_oneof![
+            Just(0.0),
+            Just(-0.0),
+            Just(1.5),
+            Just(f64::NAN),
+            Just(-f64::NAN),
+            Just(f64::INFINITY),
+            any::<f64>(),
+        ]
+    }
+
+    // Small domains, so that independently generated values are often equal.
+    fn leaf() -> impl Strategy<Value = CandyShared> {
+        prop_oneof![
+            (-2_i64..2).prop_map(|i| CandyShared::Int(i.into())),
+            (-2_i8..2).prop_map(CandyShared::Int8),
+            (-2_i16..2).prop_map(CandyShared::Int16),
+            (-2_i32..2).prop_map(CandyShared::Int32),
+            (-2_i64..2).prop_map(CandyShared::Int64),
+            prop::collection::vec(-2_i64..2, 0..3)
+                .prop_map(|i| CandyShared::Ints(i.into_iter().map(candid::Int::from).collect())),
+            (0_u64..3).prop_map(|i| CandyShared::Nat(i.into())),
+            (0_u8..3).prop_map(CandyShared::Nat8),
+            (0_u16..3).prop_map(CandyShared::Nat16),
+            (0_u32..3).prop_map(CandyShared::Nat32),
+            (0_u64..3).prop_map(CandyShared::Nat64),
+            float().prop_map(CandyShared::Float),
+            "[ab]{0,2}".prop_map(CandyShared::Text),
+            any::<bool>().prop_map(CandyShared::Bool),
+            prop::collection::vec(0_u8..2, 0..3).prop_map(CandyShared::Blob),
+            prop::collection::vec(0_u8..2, 0..3).prop_map(CandyShared::Bytes),
+            prop_oneof![
+                Just(Principal::anonymous()),
+                Just(Principal::management_canister())
+            ]
+            .prop_map(CandyShared::Principal),
+            prop::collection::vec(0_u64..3, 0..3)
+                .prop_map(|i| CandyShared::Nats(i.into_iter().map(candid::Nat::from).collect())),
+            prop::collection::vec(float(), 0..3).prop_map(CandyShared::Floats),
+        ]
+    }
+
+    // Covers all 25 variants, the collections recursively.
+    fn candy() -> impl Strategy<Value = CandyShared> {
+        leaf().prop_recursive(3, 24, 3, |inner| {
+            prop_oneof![
+                prop::collection::vec(("[ab]", inner.clone(), any::<bool>()), 0..3).prop_map(
+                    |props| {
+                        CandyShared::Class(
+                            props
+                                .into_iter()
+                                .map(|(name, value, immutable)| PropertyShared {
+                                    name,
+                                    value,
+                                    immutable,
+                                })
+                                .collect(),
+                        )
+                    }
+                ),
+                prop::option::of(inner.clone())
+                    .prop_map(|value| CandyShared::Option(value.map(Box::new))),
+                prop::collection::vec(inner.clone(), 0..3).prop_map(CandyShared::Array),
+                prop::collection::btree_map("[ab]", inner.clone(), 0..3).prop_map(CandyShared::Map),
+                prop::collection::btree_map(inner.clone(), inner.clone(), 0..3)
+                    .prop_map(CandyShared::ValueMap),
+                prop::collection::btree_set(inner, 0..3).prop_map(CandyShared::Set),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn equal_values_have_equal_hashes(a in candy(), b in candy()) {
+            if a == b {
+                prop_assert_eq!(hash(&a), hash(&b));
+            }
+            prop_assert_eq!(a == b, a.cmp(&b) == Ordering::Equal);
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+        }
+
+        #[test]
+        fn rebuilt_values_are_equal(a in candy()) {
+            prop_assert_eq!(&a, &a.clone());
+            prop_assert_eq!(hash(&a), hash(&a.clone()));
+
+            let bytes = candid::encode_one(&a).unwrap();
+            let decoded: CandyShared = candid::decode_one(&bytes).unwrap();
+            prop_assert_eq!(&decoded, &a);
+            prop_assert_eq!(hash(&decoded), hash(&a));
+
+            let unshared = a.clone().unshare().share();
+            prop_assert_eq!(&unshared, &a);
+            prop_assert_eq!(hash(&unshared), hash(&a));
+        }
+    }
+
+    #[test]
+    fn float_equality() {
+        let nan = f64::NAN.to_candy();
+        assert_eq!(nan, nan.clone());
+        assert_eq!(hash(&nan), hash(&nan.clone()));
+        assert_ne!(0.0_f64.to_candy(), (-0.0_f64).to_candy());
+        assert_eq!(
+            vec![f64::NAN, 1.0].to_candy(),
+            vec![f64::NAN, 1.0].to_candy()
+        );
+
+        // NaN works as a set member and map key.
+        let set: HashSet<CandyShared> = [nan.clone(), nan.clone()].into_iter().collect();
+        assert_eq!(set.len(), 1);
+        let mut map = HashMap::<CandyShared, CandyShared>::new();
+        map.insert(nan.clone(), 1_u8.to_candy());
+        assert_eq!(map.get(&nan), Some(&1_u8.to_candy()));
+    }
+
+    #[test]
+    fn map_hash_ignores_insertion_order() {
+        let entries = [("a", 1_u8), ("b", 2), ("c", 3)];
+        let forward: HashMap<String, CandyShared> = entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_candy()))
+            .collect();
+        let backward: HashMap<String, CandyShared> = entries
+            .iter()
+            .rev()
+            .map(|(k, v)| (k.to_string(), v.to_candy()))
+            .collect();
+        assert_eq!(forward.clone().to_candy(), backward.clone().to_candy());
+        assert_eq!(hash(&forward.to_candy()), hash(&backward.to_candy()));
+    }
+
+    #[test]
+    fn ordering() {
+        let mut values = vec![
+            vec![1_u8.to_candy(), 2_u8.to_candy()].to_candy(),
+            CandyShared::Option(None),
+            vec![1_u8.to_candy()].to_candy(),
+            f64::NAN.to_candy(),
+            (-0.0_f64).to_candy(),
+            f64::NEG_INFINITY.to_candy(),
+            "ab".to_candy(),
+            "b".to_candy(),
+            CandyShared::Option(Some(Box::new(0_u8.to_candy()))),
+            "a".to_candy(),
+            0.0_f64.to_candy(),
+        ];
+        values.sort();
+        assert_eq!(
+            values,
+            vec![
+                f64::NEG_INFINITY.to_candy(),
+                (-0.0_f64).to_candy(),
+                0.0_f64.to_candy(),
+                f64::NAN.to_candy(),
+                "a".to_candy(),
+                "ab".to_candy(),
+                "b".to_candy(),
+                CandyShared::Option(None),
+                CandyShared::Option(Some(Box::new(0_u8.to_candy()))),
+                vec![1_u8.to_candy()].to_candy(),
+                vec![1_u8.to_candy(), 2_u8.to_candy()].to_candy(),
+            ]
+        );
+
+        let prop = |name: &str, value: u8| PropertyShared {
+            name: name.to_string(),
+            value: value.to_candy(),
+            immutable: false,
+        };
+        assert!(vec![prop("a", 2)].to_candy() < vec![prop("b", 1)].to_candy());
+        assert!(vec![prop("a", 1)].to_candy() < vec![prop("a", 2)].to_candy());
+
+        // Variant rank comes first, whatever the number.
+        assert!(100_i8.to_candy() < 1_u8.to_candy());
+
+        let mut index = BTreeMap::<CandyShared, &str>::new();
+        index.insert(3_u8.to_candy(), "three");
+        index.insert(1_u8.to_candy(), "one");
+        index.insert(2_u8.to_candy(), "two");
+        let range: Vec<&str> = index
+            .range(2_u8.to_candy()..)
+            .map(|(_, name)| *name)
+            .collect();
+        assert_eq!(range, vec!["two", "three"]);
+    }
+
+    #[test]
+    fn numeric_ordering() {
+        assert_eq!(
+            5_u8.to_candy().numeric_cmp(&5_i128.to_candy()),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            u128::MAX.to_candy().numeric_cmp(&(-1_i64).to_candy()),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            2_u16.to_candy().numeric_cmp(&2.0_f64.to_candy()),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            0_u8.to_candy().numeric_cmp(&(-0.0_f64).to_candy()),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            (-1_i8).to_candy().numeric_cmp(&(-1.5_f64).to_candy()),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            1.5_f64.to_candy().numeric_cmp(&1_u8.to_candy()),
+            Some(Ordering::Greater)
+        );
+        // 2^53 + 1 is not representable as f64, an exact comparison still tells them apart.
+        assert_eq!(
+            ((1_u64 << 53) + 1)
+                .to_candy()
+                .numeric_cmp(&((1_u64 << 53) as f64).to_candy()),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            u64::MAX.to_candy().numeric_cmp(&f64::INFINITY.to_candy()),
+            Some(Ordering::Less)
+        );
+        assert_eq!(1_u8.to_candy().numeric_cmp(&f64::NAN.to_candy()), None);
+        assert_eq!(1_u8.to_candy().numeric_cmp(&true.to_candy()), None);
+    }
+
+    proptest! {
+        #[test]
+        fn numeric_cmp_matches_integers(a in any::<i32>(), b in any::<i32>()) {
+            let widths = |i: i32| {
+                vec![
+                    (i as i64).to_candy(),
+                    (i as i128).to_candy(),
+                    (i as f64).to_candy(),
+                ]
+            };
+            for x in widths(a) {
+                for y in widths(b) {
+                    prop_assert_eq!(x.numeric_cmp(&y), Some(a.cmp(&b)));
+                }
+            }
+        }
+    }
+}
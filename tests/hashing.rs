@@ -0,0 +1,168 @@
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod hashing_tests {
+    use std::collections::BTreeMap;
+
+    use pretty_assertions::assert_eq;
+
+    use ic_candy::hashing::hash_sha256;
+    use ic_candy::types::PropertyShared;
+    use ic_candy::value::{CandyShared, ToCandyValue};
+
+    fn hash_hex(value: &CandyShared) -> String {
+        hex::encode(hash_sha256(value))
+    }
+
+    // Test vectors from the ICRC-3 specification.
+    #[test]
+    fn hashing_icrc3_vectors() {
+        assert_eq!(
+            hash_hex(&42_u128.to_candy()),
+            "684888c0ebb17f374298b65ee2807526c066094c701bcc7ebbe1c1095f494fc1"
+        );
+        assert_eq!(
+            hash_hex(&(-42_i128).to_candy()),
+            "de5a6f78116eca62d7fc5ce159d23ae6b889b365a1739ad2cf36f925a140d0cc"
+        );
+        assert_eq!(
+            hash_hex(&"Hello, World!".to_candy()),
+            "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"
+        );
+        assert_eq!(
+            hash_hex(&vec![1_u8, 2, 3, 4].to_candy()),
+            "9f64a747e1b97f131fabb6b447296c9b6f0201e79fb3c5356e6c77e89b6a806a"
+        );
+        assert_eq!(
+            hash_hex(
+                &vec![
+                    3_u128.to_candy(),
+                    "foo".to_candy(),
+                    vec![5_u8, 6].to_candy()
+                ]
+                .to_candy()
+            ),
+            "514a04011caa503990d446b7dec5d79e19c221ae607fb08b2848c67734d468d6"
+        );
+    }
+
+    #[test]
+    fn hashing_integers() {
+        assert_eq!(
+            hash_hex(&0_u8.to_candy()),
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d"
+        );
+        // LEB128 of 624485 is e5 8e 26.
+        assert_eq!(
+            hash_sha256(&624485_u32.to_candy()),
+            hash_sha256(&CandyShared::Bytes(vec![0xe5, 0x8e, 0x26]))
+        );
+        // Signed LEB128 around the sign bit of the first byte.
+        for (int, sleb) in [
+            (-123456_i64, vec![0xc0, 0xbb, 0x78]),
+            (64, vec![0xc0, 0x00]),
+            (-64, vec![0x40]),
+            (-65, vec![0xbf, 0x7f]),
+        ] {
+            assert_eq!(
+                hash_sha256(&int.to_candy()),
+                hash_sha256(&CandyShared::Bytes(sleb))
+            );
+            assert_eq!(
+                hash_sha256(&int.to_candy()),
+                hash_sha256(&(int as i128).to_candy())
+            );
+        }
+    }
+
+    #[test]
+    fn hashing_bools() {
+        // `Bool` hashes as the one byte blob `1` or `0`.
+        assert_eq!(
+            hash_hex(&true.to_candy()),
+            "4bf5122f344554c53bde2ebb8cd2b7e3d1600ad631c385a5d7cce23c7785459a"
+        );
+        assert_eq!(
+            hash_sha256(&false.to_candy()),
+            hash_sha256(&CandyShared::Bytes(vec![0]))
+        );
+    }
+
+    #[test]
+    fn hashing_maps() {
+        // The map example of the ICRC-3 specification.
+        let from = hex::decode("00abcdef0012340056789a00bcdef000012345678900abcdef01").unwrap();
+        let to = hex::decode("00ab0def0012340056789a00bcdef000012345678900abcdef01").unwrap();
+        let mut map = BTreeMap::<String, CandyShared>::new();
+        map.insert("from".to_string(), from.clone().to_candy());
+        map.insert("to".to_string(), to.clone().to_candy());
+        map.insert("amount".to_string(), 42_u128.to_candy());
+        map.insert("created_at".to_string(), 1699218263_u128.to_candy());
+        map.insert("memo".to_string(), 0_u128.to_candy());
+        assert_eq!(
+            hash_hex(&map.clone().to_candy()),
+            "c56ece650e1de4269c5bdeff7875949e3e2033f85b2d193c2ff4f7f78bdcfc75"
+        );
+
+        // Class properties hash like map entries, whatever their order, widths and immutability.
+        let prop = |name: &str, value: CandyShared, immutable| PropertyShared {
+            name: name.to_string(),
+            value,
+            immutable,
+        };
+        let class = vec![
+            prop("memo", 0_u8.to_candy(), true),
+            prop("created_at", 1699218263_u64.to_candy(), false),
+            prop("amount", 42_u8.to_candy(), false),
+            prop("to", CandyShared::Bytes(to), false),
+            prop("from", CandyShared::Bytes(from), false),
+        ]
+        .to_candy();
+        assert_eq!(hash_sha256(&class), hash_sha256(&map.to_candy()));
+    }
+
+    #[test]
+    fn hashing_other_variants() {
+        let class = vec![
+            PropertyShared {
+                name: "name".to_string(),
+                value: "candy".to_candy(),
+                immutable: false,
+            },
+            PropertyShared {
+                name: "flags".to_string(),
+                value: vec![true.to_candy(), false.to_candy()].to_candy(),
+                immutable: false,
+            },
+            PropertyShared {
+                name: "opt".to_string(),
+                value: CandyShared::Option(None),
+                immutable: false,
+            },
+        ]
+        .to_candy();
+        assert_eq!(
+            hash_hex(&class),
+            "470e8de28828986b8b2aa6c23eacf5a9aff8d2d64a875b988f09ea1192798f39"
+        );
+
+        assert_eq!(
+            hash_sha256(&vec![1_u128, 2].to_candy()),
+            hash_sha256(&vec![1_u8.to_candy(), 2_u64.to_candy()].to_candy())
+        );
+        assert_eq!(
+            hash_sha256(&1.5_f64.to_candy()),
+            hash_sha256(&"1.5".to_candy())
+        );
+        assert_eq!(
+            hash_sha256(&CandyShared::Option(Some(Box::new(1_u8.to_candy())))),
+            hash_sha256(&vec![1_u8.to_candy()].to_candy())
+        );
+        // Small non-negative numbers have the same LEB128 and signed LEB128 encoding.
+        assert_eq!(hash_sha256(&1_u8.to_candy()), hash_sha256(&1_i8.to_candy()));
+        assert_ne!(
+            hash_sha256(&64_u8.to_candy()),
+            hash_sha256(&64_i8.to_candy())
+        );
+    }
+}
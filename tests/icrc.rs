@@ -0,0 +1,217 @@
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod icrc_tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use candid::Principal;
+    use pretty_assertions::assert_eq;
+
+    use ic_candy::icrc::{lossy_variants, BoolEncoding, Icrc3Options, Icrc3Value};
+    use ic_candy::types::{CandyError, PropertyShared};
+    use ic_candy::value::{CandyShared, ToCandyValue};
+
+    fn prop(name: &str, value: CandyShared, immutable: bool) -> PropertyShared {
+        PropertyShared {
+            name: name.to_string(),
+            value,
+            immutable,
+        }
+    }
+
+    fn nat(value: u64) -> Icrc3Value {
+        Icrc3Value::Nat(value.into())
+    }
+
+    fn text(value: &str) -> Icrc3Value {
+        Icrc3Value::Text(value.to_string())
+    }
+
+    fn metadata() -> CandyShared {
+        let mut map = BTreeMap::<String, CandyShared>::new();
+        map.insert("width".to_string(), 100_u16.to_candy());
+        let mut value_map = BTreeMap::<CandyShared, CandyShared>::new();
+        value_map.insert(1_u8.to_candy(), "one".to_candy());
+        vec![
+            prop("id", 7_u64.to_candy(), true),
+            prop("owner", Principal::anonymous().to_candy(), false),
+            prop("listed", true.to_candy(), false),
+            prop("price", 1.5_f64.to_candy(), false),
+            prop("delta", (-3_i8).to_candy(), false),
+            prop("display", map.to_candy(), false),
+            prop("names", value_map.to_candy(), false),
+            prop("memo", CandyShared::Option(None), false),
+            prop("bytes", CandyShared::Bytes(vec![1, 2]), false),
+            prop("nats", vec![1_u128, 2].to_candy(), false),
+        ]
+        .to_candy()
+    }
+
+    #[test]
+    fn icrc3_from_candy() {
+        assert_eq!(
+            Icrc3Value::from(metadata()),
+            Icrc3Value::Map(vec![
+                ("id".to_string(), nat(7)),
+                ("owner".to_string(), Icrc3Value::Blob(vec![4])),
+                ("listed".to_string(), nat(1)),
+                ("price".to_string(), text("1.5")),
+                ("delta".to_string(), Icrc3Value::Int((-3).into())),
+                (
+                    "display".to_string(),
+                    Icrc3Value::Map(vec![("width".to_string(), nat(100))])
+                ),
+                (
+                    "names".to_string(),
+                    Icrc3Value::Array(vec![Icrc3Value::Array(vec![nat(1), text("one")])])
+                ),
+                ("memo".to_string(), Icrc3Value::Array(vec![])),
+                ("bytes".to_string(), Icrc3Value::Blob(vec![1, 2])),
+                ("nats".to_string(), Icrc3Value::Array(vec![nat(1), nat(2)])),
+            ])
+        );
+    }
+
+    #[test]
+    fn icrc3_bool_encoding() {
+        let convert = |bool_encoding| {
+            Icrc3Value::from_candy(false.to_candy(), &Icrc3Options { bool_encoding })
+        };
+        assert_eq!(convert(BoolEncoding::Nat), nat(0));
+        assert_eq!(convert(BoolEncoding::Text), text("false"));
+        assert_eq!(convert(BoolEncoding::Blob), Icrc3Value::Blob(vec![0]));
+    }
+
+    #[test]
+    fn icrc3_to_candy() {
+        let value = Icrc3Value::Map(vec![
+            ("amount".to_string(), nat(42)),
+            ("fee".to_string(), Icrc3Value::Int((-1).into())),
+            (
+                "tags".to_string(),
+                Icrc3Value::Array(vec![text("a"), Icrc3Value::Blob(vec![1])]),
+            ),
+        ]);
+        assert_eq!(
+            CandyShared::try_from(value),
+            Ok(vec![
+                prop("amount", 42_u128.to_candy(), false),
+                prop("fee", (-1_i128).to_candy(), false),
+                prop(
+                    "tags",
+                    vec!["a".to_candy(), vec![1_u8].to_candy()].to_candy(),
+                    false
+                ),
+            ]
+            .to_candy())
+        );
+
+        let duplicate = Icrc3Value::Map(vec![("a".to_string(), nat(1)), ("a".to_string(), nat(2))]);
+        assert!(matches!(
+            CandyShared::try_from(duplicate),
+            Err(CandyError::InvalidEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn icrc3_lossy_variants() {
+        let lossy: Vec<&str> = lossy_variants(&metadata()).into_iter().collect();
+        assert_eq!(
+            lossy,
+            vec![
+                "Bool",
+                "Bytes",
+                "Class",
+                "Float",
+                "Int8",
+                "Map",
+                "Nat16",
+                "Nat64",
+                "Nat8",
+                "Nats",
+                "Option",
+                "Principal",
+                "ValueMap"
+            ]
+        );
+
+        let lossless = vec![
+            prop("amount", 42_u128.to_candy(), false),
+            prop(
+                "tags",
+                vec!["a".to_candy(), vec![1_u8].to_candy(), (-1_i128).to_candy()].to_candy(),
+                false,
+            ),
+        ]
+        .to_candy();
+        assert!(lossy_variants(&lossless).is_empty());
+        assert_eq!(
+            CandyShared::try_from(Icrc3Value::from(lossless.clone())),
+            Ok(lossless)
+        );
+
+        // Only the last of the properties with the same name is kept, so they are lossy too.
+        let duplicates = vec![
+            prop("amount", 42_u128.to_candy(), false),
+            prop("memo", "a".to_candy(), false),
+            prop("amount", 43_u128.to_candy(), false),
+        ]
+        .to_candy();
+        assert_eq!(
+            lossy_variants(&duplicates).into_iter().collect::<Vec<_>>(),
+            vec!["Class"]
+        );
+        let converted = Icrc3Value::from(duplicates.clone());
+        assert_eq!(
+            converted,
+            Icrc3Value::Map(vec![
+                ("amount".to_string(), nat(43)),
+                ("memo".to_string(), text("a")),
+            ])
+        );
+        assert_eq!(converted.hash_sha256(), duplicates.hash_sha256());
+        assert_eq!(
+            CandyShared::try_from(converted),
+            Ok(vec![
+                prop("amount", 43_u128.to_candy(), false),
+                prop("memo", "a".to_candy(), false),
+            ]
+            .to_candy())
+        );
+    }
+
+    #[test]
+    fn icrc3_hash() {
+        for value in [
+            metadata(),
+            vec![1_u8.to_candy(), true.to_candy()].to_candy(),
+            vec![1.5_f64, f64::NAN].to_candy(),
+        ] {
+            assert_eq!(
+                Icrc3Value::from(value.clone()).hash_sha256(),
+                value.hash_sha256()
+            );
+        }
+        // `Bool` always hashes as the one byte blob, which the `Text` encoding does not match.
+        let text_bools = Icrc3Options {
+            bool_encoding: BoolEncoding::Text,
+        };
+        assert!(
+            Icrc3Value::from_candy(true.to_candy(), &text_bools).hash_sha256()
+                != true.to_candy().hash_sha256()
+        );
+
+        let value = Icrc3Value::Array(vec![nat(3), text("foo"), Icrc3Value::Blob(vec![5, 6])]);
+        assert_eq!(
+            hex::encode(value.hash_sha256()),
+            "514a04011caa503990d446b7dec5d79e19c221ae607fb08b2848c67734d468d6"
+        );
+    }
+
+    #[test]
+    fn icrc3_candid() {
+        let value = Icrc3Value::from(metadata());
+        let bytes = candid::encode_one(&value).unwrap();
+        assert_eq!(candid::decode_one::<Icrc3Value>(&bytes).unwrap(), value);
+    }
+}
@@ -0,0 +1,127 @@
+#![cfg(feature = "icrc16")]
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod icrc16_tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use candid::Principal;
+    use pretty_assertions::assert_eq;
+
+    use ic_candy::icrc16::{Icrc16Property, Icrc16Value};
+    use ic_candy::types::{CandyError, PropertyShared};
+    use ic_candy::value::{CandyShared, ToCandyValue};
+
+    // One of every variant.
+    fn all_variants() -> CandyShared {
+        let mut map = BTreeMap::<String, CandyShared>::new();
+        map.insert("b".to_string(), 2_u8.to_candy());
+        map.insert("a".to_string(), 1_u8.to_candy());
+        let mut value_map = BTreeMap::<CandyShared, CandyShared>::new();
+        value_map.insert("one".to_candy(), 1_u8.to_candy());
+        value_map.insert(1_u8.to_candy(), "one".to_candy());
+        let set: BTreeSet<CandyShared> = [2_u8.to_candy(), 1_u8.to_candy()].into_iter().collect();
+        vec![
+            (-1_i128).to_candy(),
+            (-8_i8).to_candy(),
+            (-16_i16).to_candy(),
+            (-32_i32).to_candy(),
+            (-64_i64).to_candy(),
+            vec![(-1_i128), 1].to_candy(),
+            1_u128.to_candy(),
+            8_u8.to_candy(),
+            16_u16.to_candy(),
+            32_u32.to_candy(),
+            64_u64.to_candy(),
+            1.5_f64.to_candy(),
+            "candy".to_candy(),
+            true.to_candy(),
+            vec![1_u8, 2].to_candy(),
+            CandyShared::Bytes(vec![3, 4]),
+            vec![PropertyShared {
+                name: "name".to_string(),
+                value: "candy".to_candy(),
+                immutable: true,
+            }]
+            .to_candy(),
+            Principal::anonymous().to_candy(),
+            CandyShared::Option(Some(Box::new(CandyShared::Option(None)))),
+            vec![1_u128, 2].to_candy(),
+            vec![f64::INFINITY, -0.0].to_candy(),
+            map.to_candy(),
+            value_map.to_candy(),
+            set.to_candy(),
+        ]
+        .to_candy()
+    }
+
+    #[test]
+    fn icrc16_conversion() {
+        let value = all_variants();
+        let icrc16 = Icrc16Value::from(value.clone());
+        assert_eq!(CandyShared::try_from(icrc16), Ok(value));
+
+        let class = vec![PropertyShared {
+            name: "id".to_string(),
+            value: CandyShared::Set([7_u8.to_candy()].into_iter().collect()),
+            immutable: true,
+        }]
+        .to_candy();
+        assert_eq!(
+            Icrc16Value::from(class),
+            Icrc16Value::Class(vec![Icrc16Property {
+                name: "id".to_string(),
+                value: Icrc16Value::Set(vec![Icrc16Value::Nat8(7)]),
+                immutable: true,
+            }])
+        );
+    }
+
+    #[test]
+    fn icrc16_unsorted_and_duplicates() {
+        let unsorted = Icrc16Value::Map(vec![
+            ("b".to_string(), Icrc16Value::Nat8(2)),
+            ("a".to_string(), Icrc16Value::Nat8(1)),
+        ]);
+        let mut map = BTreeMap::<String, CandyShared>::new();
+        map.insert("a".to_string(), 1_u8.to_candy());
+        map.insert("b".to_string(), 2_u8.to_candy());
+        assert_eq!(CandyShared::try_from(unsorted), Ok(map.to_candy()));
+
+        for duplicate in [
+            Icrc16Value::Map(vec![
+                ("a".to_string(), Icrc16Value::Nat8(1)),
+                ("a".to_string(), Icrc16Value::Nat8(2)),
+            ]),
+            Icrc16Value::ValueMap(vec![
+                (Icrc16Value::Bool(true), Icrc16Value::Nat8(1)),
+                (Icrc16Value::Bool(true), Icrc16Value::Nat8(2)),
+            ]),
+            Icrc16Value::Array(vec![Icrc16Value::Set(vec![
+                Icrc16Value::Nat8(1),
+                Icrc16Value::Nat8(1),
+            ])]),
+        ] {
+            assert!(matches!(
+                CandyShared::try_from(duplicate),
+                Err(CandyError::InvalidEncoding(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn icrc16_candid_round_trip() {
+        let value = all_variants();
+
+        // Sent by this crate, received as ICRC-16.
+        let bytes = candid::encode_one(&value).unwrap();
+        let icrc16: Icrc16Value = candid::decode_one(&bytes).unwrap();
+        assert_eq!(icrc16, Icrc16Value::from(value.clone()));
+
+        // Sent as ICRC-16, received by this crate.
+        let bytes = candid::encode_one(&icrc16).unwrap();
+        let decoded: CandyShared = candid::decode_one(&bytes).unwrap();
+        assert_eq!(decoded, value);
+        assert_eq!(CandyShared::try_from(icrc16), Ok(value));
+    }
+}
@@ -0,0 +1,233 @@
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod idl_tests {
+    use std::collections::BTreeMap;
+
+    use candid::types::value::IDLField;
+    use candid::types::{Label, TypeEnv};
+    use candid::{CandidType, Deserialize, IDLArgs, IDLValue, Principal};
+    use pretty_assertions::assert_eq;
+
+    use ic_candy::idl::{from_idl_value, to_idl_value, IdlError, VARIANT_TAG, VARIANT_VALUE};
+    use ic_candy::types::PropertyShared;
+    use ic_candy::value::{CandyShared, ToCandyValue};
+
+    #[derive(CandidType, Deserialize, Debug, PartialEq)]
+    enum Status {
+        Pending,
+        Settled { block: u64 },
+    }
+
+    #[derive(CandidType, Deserialize, Debug, PartialEq)]
+    struct Transfer {
+        from: Principal,
+        amounts: Vec<candid::Nat>,
+        memo: Option<Vec<u8>>,
+        status: Status,
+        pair: (i8, String),
+        fee: Option<f64>,
+    }
+
+    fn transfer() -> Transfer {
+        Transfer {
+            from: Principal::anonymous(),
+            amounts: vec![1_u8.into(), 2_u8.into()],
+            memo: Some(vec![1, 2]),
+            status: Status::Settled { block: 7 },
+            pair: (-1, "candy".to_string()),
+            fee: Some(0.5),
+        }
+    }
+
+    // Decoded with the type, so that the record fields keep their names.
+    fn typed_transfer() -> IDLValue {
+        let bytes = candid::encode_one(transfer()).unwrap();
+        IDLArgs::from_bytes_with_types(&bytes, &TypeEnv::new(), &[Transfer::ty()])
+            .unwrap()
+            .args
+            .remove(0)
+    }
+
+    fn prop(name: &str, value: CandyShared, immutable: bool) -> PropertyShared {
+        PropertyShared {
+            name: name.to_string(),
+            value,
+            immutable,
+        }
+    }
+
+    #[test]
+    fn idl_from_candid_payload() {
+        let value = from_idl_value(typed_transfer()).unwrap();
+        assert_eq!(
+            value.get(&"status".parse().unwrap()),
+            Some(
+                &vec![
+                    prop(VARIANT_TAG, "Settled".to_candy(), true),
+                    prop(
+                        VARIANT_VALUE,
+                        vec![prop("block", 7_u64.to_candy(), false)].to_candy(),
+                        true
+                    ),
+                ]
+                .to_candy()
+            )
+        );
+        assert_eq!(
+            value.get(&"amounts".parse().unwrap()),
+            Some(&vec![1_u128, 2].to_candy())
+        );
+        assert_eq!(
+            value.get(&"memo".parse().unwrap()),
+            Some(&CandyShared::Option(Some(Box::new(CandyShared::Blob(
+                vec![1, 2]
+            )))))
+        );
+        assert_eq!(
+            value.get(&"pair".parse().unwrap()),
+            Some(
+                &vec![
+                    prop("0", (-1_i8).to_candy(), false),
+                    prop("1", "candy".to_candy(), false)
+                ]
+                .to_candy()
+            )
+        );
+        assert_eq!(
+            value.get(&"fee".parse().unwrap()),
+            Some(&CandyShared::Option(Some(Box::new(0.5_f64.to_candy()))))
+        );
+    }
+
+    #[test]
+    fn idl_round_trip() {
+        let untyped = IDLArgs::from_bytes(&candid::encode_one(transfer()).unwrap())
+            .unwrap()
+            .args
+            .remove(0);
+        for idl in [typed_transfer(), untyped] {
+            let value = from_idl_value(idl.clone()).unwrap();
+            let converted = to_idl_value(&value).unwrap();
+            assert_eq!(converted, idl);
+
+            let bytes = IDLArgs::new(&[converted]).to_bytes().unwrap();
+            assert_eq!(candid::decode_one::<Transfer>(&bytes).unwrap(), transfer());
+        }
+
+        let pending = IDLArgs::from_bytes(&candid::encode_one(Status::Pending).unwrap()).unwrap();
+        let value = CandyShared::from_idl_value(pending.args[0].clone()).unwrap();
+        let bytes = IDLArgs::new(&[value.to_idl_value().unwrap()])
+            .to_bytes()
+            .unwrap();
+        assert_eq!(
+            candid::decode_one::<Status>(&bytes).unwrap(),
+            Status::Pending
+        );
+
+        // `null` decodes into an empty `opt` too.
+        let bytes = IDLArgs::new(&[CandyShared::Option(None).to_idl_value().unwrap()])
+            .to_bytes()
+            .unwrap();
+        assert_eq!(candid::decode_one::<Option<u8>>(&bytes).unwrap(), None);
+    }
+
+    #[test]
+    fn idl_candy_collections() {
+        let mut map = BTreeMap::<String, CandyShared>::new();
+        map.insert("a".to_string(), 1_u8.to_candy());
+        let bytes = IDLArgs::new(&[map.to_candy().to_idl_value().unwrap()])
+            .to_bytes()
+            .unwrap();
+        assert_eq!(
+            candid::decode_one::<Vec<(String, u8)>>(&bytes).unwrap(),
+            vec![("a".to_string(), 1)]
+        );
+
+        assert_eq!(
+            to_idl_value(&CandyShared::Bytes(vec![1])),
+            Ok(IDLValue::Vec(vec![IDLValue::Nat8(1)]))
+        );
+        assert_eq!(
+            from_idl_value(IDLValue::Vec(vec![IDLValue::Nat8(1)])),
+            Ok(CandyShared::Bytes(vec![1]))
+        );
+        assert_eq!(
+            from_idl_value(IDLValue::Vec(vec![])),
+            Ok(CandyShared::Array(vec![]))
+        );
+        assert_eq!(
+            from_idl_value(IDLValue::Vec(vec![IDLValue::Nat8(1), IDLValue::Nat16(2)])),
+            Ok(vec![1_u8.to_candy(), 2_u16.to_candy()].to_candy())
+        );
+
+        // Numeric property names are field ids, and records are ordered by them.
+        let class = vec![
+            prop("b", 2_u8.to_candy(), false),
+            prop("1", 1_u8.to_candy(), false),
+        ]
+        .to_candy();
+        assert_eq!(
+            to_idl_value(&class),
+            Ok(IDLValue::Record(vec![
+                IDLField {
+                    id: Label::Id(1),
+                    val: IDLValue::Nat8(1)
+                },
+                IDLField {
+                    id: Label::Named("b".to_string()),
+                    val: IDLValue::Nat8(2)
+                },
+            ]))
+        );
+    }
+
+    #[test]
+    fn idl_other_values() {
+        assert_eq!(
+            from_idl_value(IDLValue::Number("-1_000".to_string())),
+            Ok((-1000_i128).to_candy())
+        );
+        assert_eq!(
+            from_idl_value(IDLValue::Number("42".to_string())),
+            Ok(42_u128.to_candy())
+        );
+        assert_eq!(
+            from_idl_value(IDLValue::Float32(1.5)),
+            Ok(1.5_f64.to_candy())
+        );
+        for value in [IDLValue::Null, IDLValue::Reserved, IDLValue::None] {
+            assert_eq!(from_idl_value(value), Ok(CandyShared::Option(None)));
+        }
+    }
+
+    #[test]
+    fn idl_errors() {
+        assert!(matches!(
+            from_idl_value(IDLValue::Func(
+                Principal::anonymous(),
+                "transfer".to_string()
+            )),
+            Err(IdlError::Unrepresentable(_))
+        ));
+        assert!(matches!(
+            from_idl_value(IDLValue::Opt(Box::new(IDLValue::Service(
+                Principal::anonymous()
+            )))),
+            Err(IdlError::Unrepresentable(_))
+        ));
+        assert!(matches!(
+            from_idl_value(IDLValue::Number("one".to_string())),
+            Err(IdlError::InvalidNumber(_))
+        ));
+        let class = vec![
+            prop("a", 1_u8.to_candy(), false),
+            prop("a", 2_u8.to_candy(), false),
+        ]
+        .to_candy();
+        assert_eq!(
+            to_idl_value(&class),
+            Err(IdlError::DuplicateLabel("a".to_string()))
+        );
+    }
+}
@@ -0,0 +1,286 @@
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod merge_tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use ic_candy::merge::{merge, ArrayPolicy, ConflictPolicy, MergeError, MergeStrategy};
+    use ic_candy::types::PropertyShared;
+    use ic_candy::value::{CandyShared, ToCandyValue};
+
+    fn prop(name: &str, value: CandyShared, immutable: bool) -> PropertyShared {
+        PropertyShared {
+            name: name.to_string(),
+            value,
+            immutable,
+        }
+    }
+
+    fn strategy(conflicts: ConflictPolicy, arrays: ArrayPolicy) -> MergeStrategy {
+        MergeStrategy { conflicts, arrays }
+    }
+
+    fn defaults() -> CandyShared {
+        vec![
+            prop("creator", "alice".to_candy(), true),
+            prop("name", "untitled".to_candy(), false),
+            prop("tags", vec!["art".to_candy()].to_candy(), false),
+            prop(
+                "display",
+                vec![
+                    prop("width", 100_u16.to_candy(), false),
+                    prop("height", 100_u16.to_candy(), false),
+                ]
+                .to_candy(),
+                false,
+            ),
+        ]
+        .to_candy()
+    }
+
+    fn token() -> CandyShared {
+        vec![
+            prop("name", "candy".to_candy(), false),
+            prop("tags", vec!["sweet".to_candy()].to_candy(), false),
+            prop(
+                "display",
+                vec![prop("height", 200_u16.to_candy(), true)].to_candy(),
+                false,
+            ),
+            prop("rarity", 5_u8.to_candy(), false),
+        ]
+        .to_candy()
+    }
+
+    #[test]
+    fn merge_overlay_wins() {
+        assert_eq!(
+            merge(&defaults(), &token(), MergeStrategy::default()),
+            Ok(vec![
+                prop("creator", "alice".to_candy(), true),
+                prop("name", "candy".to_candy(), false),
+                prop("tags", vec!["sweet".to_candy()].to_candy(), false),
+                prop(
+                    "display",
+                    vec![
+                        prop("width", 100_u16.to_candy(), false),
+                        prop("height", 200_u16.to_candy(), true),
+                    ]
+                    .to_candy(),
+                    false,
+                ),
+                prop("rarity", 5_u8.to_candy(), false),
+            ]
+            .to_candy())
+        );
+    }
+
+    #[test]
+    fn merge_base_wins_with_concat() {
+        assert_eq!(
+            merge(
+                &defaults(),
+                &token(),
+                strategy(ConflictPolicy::BaseWins, ArrayPolicy::Concat)
+            ),
+            Ok(vec![
+                prop("creator", "alice".to_candy(), true),
+                prop("name", "untitled".to_candy(), false),
+                prop(
+                    "tags",
+                    vec!["art".to_candy(), "sweet".to_candy()].to_candy(),
+                    false
+                ),
+                prop(
+                    "display",
+                    vec![
+                        prop("width", 100_u16.to_candy(), false),
+                        prop("height", 100_u16.to_candy(), true),
+                    ]
+                    .to_candy(),
+                    false,
+                ),
+                prop("rarity", 5_u8.to_candy(), false),
+            ]
+            .to_candy())
+        );
+    }
+
+    #[test]
+    fn merge_error_on_conflict() {
+        let error = strategy(ConflictPolicy::Error, ArrayPolicy::Concat);
+        assert_eq!(
+            merge(&defaults(), &token(), error),
+            Err(MergeError::Conflict("name".parse().unwrap()))
+        );
+
+        let overlay = vec![
+            prop("name", "untitled".to_candy(), false),
+            prop("tags", vec!["sweet".to_candy()].to_candy(), false),
+            prop(
+                "display",
+                vec![prop("depth", 1_u16.to_candy(), false)].to_candy(),
+                false,
+            ),
+        ]
+        .to_candy();
+        let merged = merge(&defaults(), &overlay, error).unwrap();
+        assert_eq!(
+            merged.get(&"tags".parse().unwrap()),
+            Some(&vec!["art".to_candy(), "sweet".to_candy()].to_candy())
+        );
+        assert_eq!(
+            merged.get(&"display.depth".parse().unwrap()),
+            Some(&1_u16.to_candy())
+        );
+    }
+
+    #[test]
+    fn merge_never_overrides_immutable() {
+        let overlay = vec![prop("creator", "bob".to_candy(), false)].to_candy();
+        for conflicts in [ConflictPolicy::OverlayWins, ConflictPolicy::BaseWins] {
+            assert_eq!(
+                merge(
+                    &defaults(),
+                    &overlay,
+                    strategy(conflicts, ArrayPolicy::Replace)
+                ),
+                Ok(defaults())
+            );
+        }
+        assert_eq!(
+            merge(
+                &defaults(),
+                &overlay,
+                strategy(ConflictPolicy::Error, ArrayPolicy::Replace)
+            ),
+            Err(MergeError::Conflict("creator".parse().unwrap()))
+        );
+
+        // Immutable containers are not merged into either.
+        let base = vec![prop(
+            "display",
+            vec![prop("width", 100_u16.to_candy(), false)].to_candy(),
+            true,
+        )]
+        .to_candy();
+        let overlay = vec![prop(
+            "display",
+            vec![prop("height", 100_u16.to_candy(), false)].to_candy(),
+            false,
+        )]
+        .to_candy();
+        assert_eq!(merge(&base, &overlay, MergeStrategy::default()), Ok(base));
+
+        // Nor are mutable values holding immutable properties replaced.
+        let base = vec![prop(
+            "meta",
+            vec![prop("id", 1_u8.to_candy(), true)].to_candy(),
+            false,
+        )]
+        .to_candy();
+        let overlay = vec![prop("meta", "gone".to_candy(), false)].to_candy();
+        assert_eq!(
+            merge(&base, &overlay, MergeStrategy::default()),
+            Ok(base.clone())
+        );
+        assert_eq!(
+            merge(
+                &base,
+                &overlay,
+                strategy(ConflictPolicy::Error, ArrayPolicy::Replace)
+            ),
+            Err(MergeError::Conflict("meta".parse().unwrap()))
+        );
+        let nested = vec![prop(
+            "meta",
+            vec![vec![prop("id", 1_u8.to_candy(), true)].to_candy()].to_candy(),
+            false,
+        )]
+        .to_candy();
+        assert_eq!(
+            merge(&nested, &overlay, MergeStrategy::default()),
+            Ok(nested)
+        );
+    }
+
+    #[test]
+    fn merge_maps() {
+        let mut base = HashMap::<String, CandyShared>::new();
+        base.insert(
+            "inner".to_string(),
+            vec![prop("a", 1_u8.to_candy(), false)].to_candy(),
+        );
+        base.insert("kept".to_string(), 1_u8.to_candy());
+        let mut overlay = HashMap::<String, CandyShared>::new();
+        overlay.insert(
+            "inner".to_string(),
+            vec![prop("b", 2_u8.to_candy(), false)].to_candy(),
+        );
+        overlay.insert("added".to_string(), 2_u8.to_candy());
+
+        let mut expected = HashMap::<String, CandyShared>::new();
+        expected.insert(
+            "inner".to_string(),
+            vec![
+                prop("a", 1_u8.to_candy(), false),
+                prop("b", 2_u8.to_candy(), false),
+            ]
+            .to_candy(),
+        );
+        expected.insert("kept".to_string(), 1_u8.to_candy());
+        expected.insert("added".to_string(), 2_u8.to_candy());
+        assert_eq!(
+            merge(
+                &base.to_candy(),
+                &overlay.to_candy(),
+                MergeStrategy::default()
+            ),
+            Ok(expected.to_candy())
+        );
+
+        let mut base = HashMap::<CandyShared, CandyShared>::new();
+        base.insert(1_u8.to_candy(), "one".to_candy());
+        base.insert(2_u8.to_candy(), "two".to_candy());
+        let mut overlay = HashMap::<CandyShared, CandyShared>::new();
+        overlay.insert(1_u8.to_candy(), "uno".to_candy());
+        overlay.insert(2_u8.to_candy(), "dos".to_candy());
+        assert_eq!(
+            merge(
+                &base.to_candy(),
+                &overlay.to_candy(),
+                strategy(ConflictPolicy::Error, ArrayPolicy::Replace)
+            ),
+            Err(MergeError::Conflict("[{\"Nat8\":1}]".parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn merge_mismatched_variants() {
+        assert_eq!(
+            merge(
+                &1_u8.to_candy(),
+                &"one".to_candy(),
+                MergeStrategy::default()
+            ),
+            Ok("one".to_candy())
+        );
+        assert_eq!(
+            merge(
+                &vec![1_u8.to_candy()].to_candy(),
+                &2_u8.to_candy(),
+                strategy(ConflictPolicy::BaseWins, ArrayPolicy::Concat)
+            ),
+            Ok(vec![1_u8.to_candy()].to_candy())
+        );
+    }
+
+    #[test]
+    fn merge_error_candid_round_trip() {
+        let error = MergeError::Conflict("display.width".parse().unwrap());
+        let bytes = candid::encode_one(&error).unwrap();
+        assert_eq!(candid::decode_one::<MergeError>(&bytes).unwrap(), error);
+    }
+}
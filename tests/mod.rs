@@ -1,2 +1,13 @@
 mod collections;
 mod conversion;
+mod properties;
+mod path;
+mod diff;
+mod patch;
+mod merge;
+mod equality;
+mod hashing;
+mod icrc;
+mod icrc16;
+mod idl;
+mod ser_de;
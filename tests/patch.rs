@@ -0,0 +1,419 @@
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod patch_tests {
+    use std::collections::{HashMap, HashSet};
+
+    use pretty_assertions::assert_eq;
+
+    use ic_candy::patch::{CandyPatch, PatchError};
+    use ic_candy::path::CandyPath;
+    use ic_candy::types::PropertyShared;
+    use ic_candy::value::{CandyShared, ToCandyValue};
+
+    fn prop(name: &str, value: CandyShared, immutable: bool) -> PropertyShared {
+        PropertyShared {
+            name: name.to_string(),
+            value,
+            immutable,
+        }
+    }
+
+    fn path(path: &str) -> CandyPath {
+        path.parse().unwrap()
+    }
+
+    fn metadata() -> CandyShared {
+        vec![
+            prop("id", 1_u8.to_candy(), true),
+            prop(
+                "attributes",
+                vec![prop("color", "red".to_candy(), false)].to_candy(),
+                false,
+            ),
+            prop(
+                "tags",
+                vec!["a".to_candy(), "c".to_candy()].to_candy(),
+                false,
+            ),
+            prop(
+                "locked",
+                vec![prop("owner", "alice".to_candy(), false)].to_candy(),
+                true,
+            ),
+        ]
+        .to_candy()
+    }
+
+    #[test]
+    fn patch_add() {
+        let mut value = metadata();
+        value
+            .apply_patch(&[
+                CandyPatch::Add {
+                    path: path("tags[1]"),
+                    value: "b".to_candy(),
+                },
+                CandyPatch::Add {
+                    path: path("tags[3]"),
+                    value: "d".to_candy(),
+                },
+                CandyPatch::Add {
+                    path: path("attributes.size"),
+                    value: 3_u8.to_candy(),
+                },
+                CandyPatch::Add {
+                    path: path("attributes.color"),
+                    value: "blue".to_candy(),
+                },
+            ])
+            .unwrap();
+        assert_eq!(
+            value.get(&path("tags")),
+            Some(
+                &vec![
+                    "a".to_candy(),
+                    "b".to_candy(),
+                    "c".to_candy(),
+                    "d".to_candy()
+                ]
+                .to_candy()
+            )
+        );
+        assert_eq!(
+            value.get(&path("attributes")),
+            Some(
+                &vec![
+                    prop("color", "blue".to_candy(), false),
+                    prop("size", 3_u8.to_candy(), false)
+                ]
+                .to_candy()
+            )
+        );
+
+        assert_eq!(
+            value.apply_patch(&[CandyPatch::Add {
+                path: path("tags[9]"),
+                value: "z".to_candy(),
+            }]),
+            Err(PatchError::NotFound(path("tags[9]")))
+        );
+        assert_eq!(
+            value.apply_patch(&[CandyPatch::Add {
+                path: path("missing.key"),
+                value: "z".to_candy(),
+            }]),
+            Err(PatchError::NotFound(path("missing")))
+        );
+        assert_eq!(
+            value.apply_patch(&[CandyPatch::Add {
+                path: path("tags[0].key"),
+                value: "z".to_candy(),
+            }]),
+            Err(PatchError::InvalidTarget(path("tags[0].key")))
+        );
+    }
+
+    #[test]
+    fn patch_remove_and_replace() {
+        let mut value = metadata();
+        value
+            .apply_patch(&[
+                CandyPatch::Remove {
+                    path: path("tags[0]"),
+                },
+                CandyPatch::Replace {
+                    path: path("attributes.color"),
+                    value: "green".to_candy(),
+                },
+            ])
+            .unwrap();
+        assert_eq!(
+            value.get(&path("tags")),
+            Some(&vec!["c".to_candy()].to_candy())
+        );
+        assert_eq!(
+            value.get(&path("attributes.color")),
+            Some(&"green".to_candy())
+        );
+
+        assert_eq!(
+            value.apply_patch(&[CandyPatch::Replace {
+                path: path("attributes.size"),
+                value: 1_u8.to_candy(),
+            }]),
+            Err(PatchError::NotFound(path("attributes.size")))
+        );
+        assert_eq!(
+            value.apply_patch(&[CandyPatch::Remove {
+                path: CandyPath::root()
+            }]),
+            Err(PatchError::InvalidTarget(CandyPath::root()))
+        );
+    }
+
+    #[test]
+    fn patch_collections() {
+        let mut map = HashMap::<String, CandyShared>::new();
+        map.insert("a".to_string(), 1_u8.to_candy());
+        let mut value_map = HashMap::<CandyShared, CandyShared>::new();
+        value_map.insert(1_u8.to_candy(), "one".to_candy());
+        let mut set = HashSet::<CandyShared>::new();
+        set.insert(1_u8.to_candy());
+        let mut value = vec![
+            prop("map", map.to_candy(), false),
+            prop("value_map", value_map.to_candy(), false),
+            prop("set", set.to_candy(), false),
+        ]
+        .to_candy();
+
+        value
+            .apply_patch(&[
+                CandyPatch::Move {
+                    from: path("map.a"),
+                    path: path("map.b"),
+                },
+                CandyPatch::Replace {
+                    path: path("value_map[{\"Nat8\":1}]"),
+                    value: "uno".to_candy(),
+                },
+                CandyPatch::Replace {
+                    path: path("set[{\"Nat8\":1}]"),
+                    value: 2_u8.to_candy(),
+                },
+            ])
+            .unwrap();
+
+        let mut expected_map = HashMap::<String, CandyShared>::new();
+        expected_map.insert("b".to_string(), 1_u8.to_candy());
+        let mut expected_value_map = HashMap::<CandyShared, CandyShared>::new();
+        expected_value_map.insert(1_u8.to_candy(), "uno".to_candy());
+        let mut expected_set = HashSet::<CandyShared>::new();
+        expected_set.insert(2_u8.to_candy());
+        assert_eq!(
+            value,
+            vec![
+                prop("map", expected_map.to_candy(), false),
+                prop("value_map", expected_value_map.to_candy(), false),
+                prop("set", expected_set.to_candy(), false),
+            ]
+            .to_candy()
+        );
+    }
+
+    #[test]
+    fn patch_move_copy_and_test() {
+        let mut value = metadata();
+        value
+            .apply_patch(&[
+                CandyPatch::Test {
+                    path: path("attributes.color"),
+                    value: "red".to_candy(),
+                },
+                CandyPatch::Copy {
+                    from: path("attributes.color"),
+                    path: path("tags[0]"),
+                },
+                CandyPatch::Move {
+                    from: path("tags[2]"),
+                    path: path("attributes.tag"),
+                },
+            ])
+            .unwrap();
+        assert_eq!(
+            value.get(&path("tags")),
+            Some(&vec!["red".to_candy(), "a".to_candy()].to_candy())
+        );
+        assert_eq!(value.get(&path("attributes.tag")), Some(&"c".to_candy()));
+
+        assert_eq!(
+            value.apply_patch(&[CandyPatch::Test {
+                path: path("attributes.color"),
+                value: "blue".to_candy(),
+            }]),
+            Err(PatchError::TestFailed(path("attributes.color")))
+        );
+        assert_eq!(
+            value.apply_patch(&[CandyPatch::Move {
+                from: path("attributes"),
+                path: path("attributes.nested"),
+            }]),
+            Err(PatchError::InvalidTarget(path("attributes.nested")))
+        );
+    }
+
+    #[test]
+    fn patch_immutable() {
+        let mut value = metadata();
+        let immutable = |patch: CandyPatch, at: &str| {
+            assert_eq!(
+                metadata().apply_patch(&[patch]),
+                Err(PatchError::Immutable(path(at)))
+            );
+        };
+        immutable(
+            CandyPatch::Replace {
+                path: path("id"),
+                value: 2_u8.to_candy(),
+            },
+            "id",
+        );
+        immutable(CandyPatch::Remove { path: path("id") }, "id");
+        immutable(
+            CandyPatch::Add {
+                path: path("locked.owner"),
+                value: "bob".to_candy(),
+            },
+            "locked",
+        );
+        immutable(
+            CandyPatch::Move {
+                from: path("locked.owner"),
+                path: path("attributes.owner"),
+            },
+            "locked",
+        );
+        immutable(
+            CandyPatch::Copy {
+                from: path("attributes"),
+                path: path("locked"),
+            },
+            "locked",
+        );
+
+        // The target is checked after the removal shifted the items behind `from`.
+        let mut items = vec![
+            vec![prop("name", "a".to_candy(), false)].to_candy(),
+            vec![prop("name", "b".to_candy(), false)].to_candy(),
+            vec![prop("name", "locked".to_candy(), true)].to_candy(),
+        ]
+        .to_candy();
+        let before = items.clone();
+        assert_eq!(
+            items.apply_patch(&[CandyPatch::Move {
+                from: path("[0]"),
+                path: path("[1].name"),
+            }]),
+            Err(PatchError::Immutable(path("[1].name")))
+        );
+        assert_eq!(items, before);
+
+        // Mutable values holding immutable properties can not be removed or replaced as a whole.
+        let nested = || {
+            vec![prop(
+                "meta",
+                vec![prop("id", 1_u8.to_candy(), true)].to_candy(),
+                false,
+            )]
+            .to_candy()
+        };
+        let refused = |patch: CandyPatch, at: &str| {
+            let mut value = nested();
+            assert_eq!(
+                value.apply_patch(&[patch]),
+                Err(PatchError::Immutable(path(at)))
+            );
+            assert_eq!(value, nested());
+        };
+        refused(CandyPatch::Remove { path: path("meta") }, "meta");
+        refused(
+            CandyPatch::Replace {
+                path: path("meta"),
+                value: 5_u8.to_candy(),
+            },
+            "meta",
+        );
+        refused(
+            CandyPatch::Add {
+                path: path("meta"),
+                value: 5_u8.to_candy(),
+            },
+            "meta",
+        );
+        refused(
+            CandyPatch::Move {
+                from: path("meta"),
+                path: path("old"),
+            },
+            "meta",
+        );
+        refused(
+            CandyPatch::Copy {
+                from: path("meta.id"),
+                path: path("meta"),
+            },
+            "meta",
+        );
+        refused(
+            CandyPatch::Replace {
+                path: CandyPath::root(),
+                value: 5_u8.to_candy(),
+            },
+            "",
+        );
+        refused(
+            CandyPatch::Add {
+                path: CandyPath::root(),
+                value: 5_u8.to_candy(),
+            },
+            "",
+        );
+
+        // Reading immutable properties is allowed.
+        value
+            .apply_patch(&[
+                CandyPatch::Test {
+                    path: path("id"),
+                    value: 1_u8.to_candy(),
+                },
+                CandyPatch::Copy {
+                    from: path("locked.owner"),
+                    path: path("attributes.owner"),
+                },
+            ])
+            .unwrap();
+        assert_eq!(
+            value.get(&path("attributes.owner")),
+            Some(&"alice".to_candy())
+        );
+    }
+
+    #[test]
+    fn patch_is_atomic() {
+        let mut value = metadata();
+        assert_eq!(
+            value.apply_patch(&[
+                CandyPatch::Remove { path: path("tags") },
+                CandyPatch::Replace {
+                    path: path("attributes.color"),
+                    value: "blue".to_candy(),
+                },
+                CandyPatch::Test {
+                    path: path("attributes.color"),
+                    value: "red".to_candy(),
+                },
+            ]),
+            Err(PatchError::TestFailed(path("attributes.color")))
+        );
+        assert_eq!(value, metadata());
+    }
+
+    #[test]
+    fn patch_candid_round_trip() {
+        let patches = vec![
+            CandyPatch::Add {
+                path: path("tags[0]"),
+                value: "x".to_candy(),
+            },
+            CandyPatch::Move {
+                from: path("a"),
+                path: path("b[\"c.d\"]"),
+            },
+        ];
+        let bytes = candid::encode_one(&patches).unwrap();
+        assert_eq!(
+            candid::decode_one::<Vec<CandyPatch>>(&bytes).unwrap(),
+            patches
+        );
+    }
+}
@@ -0,0 +1,187 @@
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod path_tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use ic_candy::path::{CandyPath, PathSegment};
+    use ic_candy::types::{CandyError, PropertyShared};
+    use ic_candy::value::{CandyShared, ToCandyValue};
+
+    fn path(path: &str) -> CandyPath {
+        path.parse().unwrap()
+    }
+
+    fn metadata() -> CandyShared {
+        let mut map = HashMap::<String, CandyShared>::new();
+        map.insert("icp".to_string(), 1_u8.to_candy());
+        let mut value_map = HashMap::<CandyShared, CandyShared>::new();
+        value_map.insert("text".to_candy(), 2_u8.to_candy());
+        value_map.insert(3_u8.to_candy(), 3_u8.to_candy());
+        vec![PropertyShared {
+            name: "metadata".to_string(),
+            value: vec![
+                PropertyShared {
+                    name: "attributes".to_string(),
+                    value: vec![
+                        "zero".to_candy(),
+                        Some(Box::new(
+                            vec![PropertyShared {
+                                name: "value".to_string(),
+                                value: "one".to_candy(),
+                                immutable: true,
+                            }]
+                            .to_candy(),
+                        ))
+                        .to_candy(),
+                    ]
+                    .to_candy(),
+                    immutable: false,
+                },
+                PropertyShared {
+                    name: "map".to_string(),
+                    value: map.to_candy(),
+                    immutable: false,
+                },
+                PropertyShared {
+                    name: "value_map".to_string(),
+                    value: value_map.to_candy(),
+                    immutable: false,
+                },
+            ]
+            .to_candy(),
+            immutable: false,
+        }]
+        .to_candy()
+    }
+
+    #[test]
+    fn parse_path() {
+        assert_eq!(path("").segments(), &[]);
+        assert_eq!(
+            path("[0][\"a\\\"b\"].c").segments(),
+            &[
+                PathSegment::Index(0),
+                PathSegment::Key("a\"b".to_string()),
+                PathSegment::Key("c".to_string()),
+            ]
+        );
+        for text in ["a.b[1]", "[2].x", "[\"a.b\"][\"\"]", "a[\"\\\\\"]"] {
+            assert_eq!(path(text).to_string(), text);
+        }
+        for text in [
+            ".a", "a.", "a..b", "a[", "a[x]", "a[1", "a[1]b", "[\"a", "a\"b",
+        ] {
+            assert_eq!(
+                text.parse::<CandyPath>(),
+                Err(CandyError::InvalidPath(text.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn get_path() {
+        let value = metadata();
+        assert_eq!(value.get(&path("")), Some(&value));
+        assert_eq!(
+            value.get(&path("metadata.attributes[0]")),
+            Some(&"zero".to_candy())
+        );
+        assert_eq!(
+            value.get(&path("metadata.attributes[1].value")),
+            Some(&"one".to_candy())
+        );
+        assert_eq!(value.get(&path("metadata.map.icp")), Some(&1_u8.to_candy()));
+        assert_eq!(
+            value.get(&path("metadata.value_map.text")),
+            Some(&2_u8.to_candy())
+        );
+        assert_eq!(value.get(&path("metadata.attributes[2]")), None);
+        assert_eq!(value.get(&path("metadata.missing")), None);
+        assert_eq!(value.get(&path("metadata[0]")), None);
+        assert_eq!(value.get(&path("metadata.attributes.value")), None);
+    }
+
+    #[test]
+    fn set_and_remove_path() {
+        let mut value = metadata();
+
+        *value.get_mut(&path("metadata.map.icp")).unwrap() = 5_u8.to_candy();
+        assert_eq!(value.get(&path("metadata.map.icp")), Some(&5_u8.to_candy()));
+
+        assert_eq!(
+            value.set(&path("metadata.attributes[1].value"), "uno".to_candy()),
+            Ok(Some("one".to_candy()))
+        );
+        assert_eq!(
+            value.set(&path("metadata.attributes[2]"), "two".to_candy()),
+            Ok(None)
+        );
+        assert_eq!(
+            value.set(&path("metadata.name"), "candy".to_candy()),
+            Ok(None)
+        );
+        assert_eq!(
+            value.set(&path("metadata.value_map.new"), 4_u8.to_candy()),
+            Ok(None)
+        );
+        assert_eq!(
+            value.get(&path("metadata.attributes[2]")),
+            Some(&"two".to_candy())
+        );
+        assert_eq!(value.get(&path("metadata.name")), Some(&"candy".to_candy()));
+        assert_eq!(
+            value.set(&path("metadata.attributes[4]"), "four".to_candy()),
+            Err(CandyError::PathNotFound(
+                "metadata.attributes[4]".to_string()
+            ))
+        );
+        assert!(value
+            .set(&path("metadata.missing.name"), "x".to_candy())
+            .is_err());
+
+        assert_eq!(
+            value.remove(&path("metadata.attributes[0]")),
+            Some("zero".to_candy())
+        );
+        assert_eq!(
+            value.get(&path("metadata.attributes[0].value")),
+            Some(&"uno".to_candy())
+        );
+        assert_eq!(
+            value.remove(&path("metadata.map.icp")),
+            Some(5_u8.to_candy())
+        );
+        assert_eq!(
+            value.remove(&path("metadata.value_map.text")),
+            Some(2_u8.to_candy())
+        );
+        assert_eq!(value.remove(&path("metadata.missing")), None);
+        assert_eq!(value.remove(&path("")), None);
+
+        assert_eq!(
+            value.get(&path("metadata.value_map[{\"Nat8\":3}]")),
+            Some(&3_u8.to_candy())
+        );
+
+        // Set members
+        let mut set: CandyShared = [1_u8.to_candy()]
+            .into_iter()
+            .collect::<std::collections::HashSet<CandyShared>>()
+            .to_candy();
+        let member = CandyPath::root().join(PathSegment::Value(1_u8.to_candy()));
+        assert_eq!(set.get(&member), Some(&1_u8.to_candy()));
+        assert_eq!(set.set(&member, 2_u8.to_candy()), Ok(Some(1_u8.to_candy())));
+        assert_eq!(set.get(&member), None);
+        assert_eq!(set.remove(&path("[{\"Nat8\":2}]")), Some(2_u8.to_candy()));
+
+        let mut root = 1_u8.to_candy();
+        assert_eq!(
+            root.set(&path(""), 2_u8.to_candy()),
+            Ok(Some(1_u8.to_candy()))
+        );
+        assert_eq!(root, 2_u8.to_candy());
+    }
+}
@@ -0,0 +1,163 @@
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod properties_tests {
+    use pretty_assertions::assert_eq;
+
+    use ic_candy::properties::{get_properties, update_properties};
+    use ic_candy::types::{PropertyError, PropertyShared, Query, Update, UpdateMode};
+    use ic_candy::value::{CandyShared, ToCandyValue};
+
+    fn prop(name: &str, value: CandyShared, immutable: bool) -> PropertyShared {
+        PropertyShared {
+            name: name.to_string(),
+            value,
+            immutable,
+        }
+    }
+
+    fn metadata() -> Vec<PropertyShared> {
+        vec![
+            prop("id", 1_u64.to_candy(), true),
+            prop("name", "candy".to_candy(), false),
+            prop(
+                "attributes",
+                vec![
+                    prop("color", "red".to_candy(), false),
+                    prop("size", 42_u32.to_candy(), true),
+                ]
+                .to_candy(),
+                false,
+            ),
+        ]
+    }
+
+    #[test]
+    fn get_properties_nested() {
+        let queries = vec![
+            Query {
+                name: "name".to_string(),
+                next: vec![],
+            },
+            Query {
+                name: "attributes".to_string(),
+                next: vec![Query {
+                    name: "size".to_string(),
+                    next: vec![],
+                }],
+            },
+        ];
+        assert_eq!(
+            get_properties(&metadata(), &queries),
+            Ok(vec![
+                prop("name", "candy".to_candy(), false),
+                prop(
+                    "attributes",
+                    vec![prop("size", 42_u32.to_candy(), true)].to_candy(),
+                    false
+                ),
+            ])
+        );
+
+        let missing = Query {
+            name: "missing".to_string(),
+            next: vec![],
+        };
+        assert_eq!(
+            get_properties(&metadata(), &[missing]),
+            Err(PropertyError::NotFound)
+        );
+
+        let not_a_class = Query {
+            name: "name".to_string(),
+            next: vec![Query {
+                name: "first".to_string(),
+                next: vec![],
+            }],
+        };
+        assert_eq!(
+            get_properties(&metadata(), &[not_a_class]),
+            Err(PropertyError::NotFound)
+        );
+    }
+
+    #[test]
+    fn update_properties_set_and_next() {
+        let updates = vec![
+            Update {
+                name: "name".to_string(),
+                mode: UpdateMode::Set("sugar".to_candy()),
+            },
+            Update {
+                name: "description".to_string(),
+                mode: UpdateMode::Set("sweet".to_candy()),
+            },
+            Update {
+                name: "attributes".to_string(),
+                mode: UpdateMode::Next(vec![Update {
+                    name: "color".to_string(),
+                    mode: UpdateMode::Set("blue".to_candy()),
+                }]),
+            },
+        ];
+        assert_eq!(
+            update_properties(&metadata(), &updates),
+            Ok(vec![
+                prop("id", 1_u64.to_candy(), true),
+                prop("name", "sugar".to_candy(), false),
+                prop(
+                    "attributes",
+                    vec![
+                        prop("color", "blue".to_candy(), false),
+                        prop("size", 42_u32.to_candy(), true),
+                    ]
+                    .to_candy(),
+                    false,
+                ),
+                prop("description", "sweet".to_candy(), false),
+            ])
+        );
+    }
+
+    #[test]
+    fn update_properties_errors() {
+        let immutable = Update {
+            name: "id".to_string(),
+            mode: UpdateMode::Set(2_u64.to_candy()),
+        };
+        assert_eq!(
+            update_properties(&metadata(), &[immutable]),
+            Err(PropertyError::Immutable)
+        );
+
+        let nested_immutable = Update {
+            name: "attributes".to_string(),
+            mode: UpdateMode::Next(vec![Update {
+                name: "size".to_string(),
+                mode: UpdateMode::Set(1_u32.to_candy()),
+            }]),
+        };
+        assert_eq!(
+            update_properties(&metadata(), &[nested_immutable]),
+            Err(PropertyError::Immutable)
+        );
+
+        let nested_missing = Update {
+            name: "missing".to_string(),
+            mode: UpdateMode::Next(vec![]),
+        };
+        assert_eq!(
+            update_properties(&metadata(), &[nested_missing]),
+            Err(PropertyError::NotFound)
+        );
+
+        let nested_not_a_class = Update {
+            name: "name".to_string(),
+            mode: UpdateMode::Next(vec![]),
+        };
+        assert_eq!(
+            update_properties(&metadata(), &[nested_not_a_class]),
+            Err(PropertyError::NotFound)
+        );
+    }
+}
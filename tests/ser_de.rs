@@ -0,0 +1,218 @@
+#![allow(unused_imports)]
+
+#[cfg(test)]
+mod ser_de_tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use candid::Principal;
+    use pretty_assertions::assert_eq;
+    use serde::{Deserialize, Serialize};
+
+    use ic_candy::idl::{VARIANT_TAG, VARIANT_VALUE};
+    use ic_candy::types::{CandyError, PropertyShared};
+    use ic_candy::value::{CandyShared, ToCandyValue};
+    use ic_candy::{from_candy_value, to_candy_value};
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    enum Status {
+        Minted,
+        Burned(u64),
+        Listed(u64, String),
+        Transferred { from: Principal, to: Principal },
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Id(u32);
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Token {
+        id: Id,
+        owner: Principal,
+        royalty: Option<f64>,
+        history: Vec<Status>,
+        attributes: BTreeMap<String, String>,
+        ranks: HashMap<u16, i128>,
+        pair: (bool, char),
+        marker: (),
+    }
+
+    fn prop(name: &str, value: CandyShared) -> PropertyShared {
+        PropertyShared {
+            name: name.to_string(),
+            value,
+            immutable: false,
+        }
+    }
+
+    fn tagged(label: &str, value: CandyShared) -> CandyShared {
+        vec![
+            PropertyShared {
+                name: VARIANT_TAG.to_string(),
+                value: label.to_candy(),
+                immutable: true,
+            },
+            PropertyShared {
+                name: VARIANT_VALUE.to_string(),
+                value,
+                immutable: true,
+            },
+        ]
+        .to_candy()
+    }
+
+    fn token() -> Token {
+        Token {
+            id: Id(7),
+            owner: Principal::anonymous(),
+            royalty: Some(2.5),
+            history: vec![
+                Status::Minted,
+                Status::Burned(1),
+                Status::Listed(2, "ICP".to_string()),
+                Status::Transferred {
+                    from: Principal::anonymous(),
+                    to: Principal::management_canister(),
+                },
+            ],
+            attributes: BTreeMap::from([("color".to_string(), "red".to_string())]),
+            ranks: HashMap::from([(1, -1)]),
+            pair: (true, 'x'),
+            marker: (),
+        }
+    }
+
+    #[test]
+    fn ser_de_to_candy_value() {
+        let mut ranks = HashMap::new();
+        ranks.insert(1_u16.to_candy(), (-1_i128).to_candy());
+        let mut attributes = BTreeMap::new();
+        attributes.insert("color".to_string(), "red".to_candy());
+        let anonymous = CandyShared::Bytes(Principal::anonymous().as_slice().to_vec());
+        assert_eq!(
+            to_candy_value(&token()),
+            Ok(vec![
+                prop("id", 7_u32.to_candy()),
+                prop("owner", anonymous.clone()),
+                prop(
+                    "royalty",
+                    CandyShared::Option(Some(Box::new(2.5_f64.to_candy())))
+                ),
+                prop(
+                    "history",
+                    vec![
+                        tagged("Minted", CandyShared::Option(None)),
+                        tagged("Burned", 1_u64.to_candy()),
+                        tagged(
+                            "Listed",
+                            vec![2_u64.to_candy(), "ICP".to_candy()].to_candy()
+                        ),
+                        tagged(
+                            "Transferred",
+                            vec![
+                                prop("from", anonymous),
+                                prop(
+                                    "to",
+                                    CandyShared::Bytes(
+                                        Principal::management_canister().as_slice().to_vec()
+                                    )
+                                ),
+                            ]
+                            .to_candy()
+                        ),
+                    ]
+                    .to_candy()
+                ),
+                prop("attributes", CandyShared::Map(attributes)),
+                prop("ranks", ranks.to_candy()),
+                prop("pair", vec![true.to_candy(), "x".to_candy()].to_candy()),
+                prop("marker", CandyShared::Option(None)),
+            ]
+            .to_candy())
+        );
+    }
+
+    #[test]
+    fn ser_de_round_trip() {
+        let value = to_candy_value(&token()).unwrap();
+        assert_eq!(from_candy_value::<Token>(value), Ok(token()));
+
+        assert_eq!(
+            from_candy_value::<Vec<Option<String>>>(
+                to_candy_value(&vec![None, Some("a")]).unwrap()
+            ),
+            Ok(vec![None, Some("a".to_string())])
+        );
+        assert_eq!(
+            from_candy_value::<u128>(to_candy_value(&u128::MAX).unwrap()),
+            Ok(u128::MAX)
+        );
+    }
+
+    #[test]
+    fn ser_de_from_other_shapes() {
+        // Numbers of other widths, typed arrays and byte collections.
+        assert_eq!(from_candy_value::<u16>(300_u128.to_candy()), Ok(300));
+        assert_eq!(from_candy_value::<f64>(2_u8.to_candy()), Ok(2.0));
+        assert_eq!(
+            from_candy_value::<Vec<u8>>(CandyShared::Blob(vec![1, 2])),
+            Ok(vec![1, 2])
+        );
+        assert_eq!(
+            from_candy_value::<Vec<i32>>(vec![1_i128, -2].to_candy()),
+            Ok(vec![1, -2])
+        );
+
+        // Classes as maps and tuples, plain values as options and text as unit variants.
+        let class = vec![prop("0", "1".to_candy()), prop("1", "a".to_candy())].to_candy();
+        assert_eq!(
+            from_candy_value::<(String, String)>(class.clone()),
+            Ok(("1".to_string(), "a".to_string()))
+        );
+        assert_eq!(
+            from_candy_value::<BTreeMap<String, String>>(class),
+            Ok(BTreeMap::from([
+                ("0".to_string(), "1".to_string()),
+                ("1".to_string(), "a".to_string())
+            ]))
+        );
+        assert_eq!(from_candy_value::<Option<u8>>(1_u8.to_candy()), Ok(Some(1)));
+        assert_eq!(
+            from_candy_value::<Status>("Minted".to_candy()),
+            Ok(Status::Minted)
+        );
+        assert_eq!(
+            from_candy_value::<Principal>(Principal::anonymous().to_candy()),
+            Ok(Principal::anonymous())
+        );
+    }
+
+    #[test]
+    fn ser_de_errors() {
+        assert_eq!(
+            from_candy_value::<u8>(300_u16.to_candy()),
+            Err(CandyError::Custom(
+                "invalid value: integer `300`, expected u8".to_string()
+            ))
+        );
+        assert_eq!(
+            from_candy_value::<Id>(CandyShared::Class(vec![])),
+            Err(CandyError::Custom(
+                "invalid type: map, expected u32".to_string()
+            ))
+        );
+        assert_eq!(
+            from_candy_value::<Token>(CandyShared::Class(vec![])),
+            Err(CandyError::MissingField("id".to_string()))
+        );
+        assert_eq!(
+            from_candy_value::<Status>(tagged("Minted", 1_u8.to_candy())),
+            Err(CandyError::UnsupportedVariant("Nat8".to_string()))
+        );
+        assert_eq!(
+            from_candy_value::<(u8, u8)>(vec![1_u8.to_candy()].to_candy()),
+            Err(CandyError::Custom(
+                "invalid length 1, expected a tuple of size 2".to_string()
+            ))
+        );
+    }
+}